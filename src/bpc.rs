@@ -0,0 +1,536 @@
+//! Decoder for the time code of BPC (Shangqiu, China, 68.5 kHz).
+//!
+//! BPC transmits Beijing time (UTC+8) without daylight saving time. Each second carries
+//! a quaternary symbol (0 through 3, encoded as a pulse of 100 through 400 ms), and
+//! each minute consists of three identical 20-second frames which only differ in their
+//! frame index. The time code describes the minute it is transmitted in.
+
+use crate::radio_datetime_helpers::{
+    add_minute_day_of_year, get_day_of_year, get_month_day, get_parity, is_leap_year,
+    sub_minute_day_of_year,
+};
+use crate::{FieldStatus, RadioDateTimeUtils};
+
+/// Number of symbols (seconds) in a frame.
+pub const FRAME_SIZE: usize = 20;
+
+/// Number of frames in a minute.
+pub const FRAMES_PER_MINUTE: usize = 3;
+
+/// Symbols of one frame, indexed by second in the frame. Symbol 0 is the frame marker
+/// (the missing pulse) and should be None.
+pub type SymbolBuffer = [Option<u8>; FRAME_SIZE];
+
+/// Per-field outcome of decoding one BPC minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    /// Number of frames which took part in the time vote
+    pub time_frames: u8,
+    /// Number of frames which took part in the date vote
+    pub date_frames: u8,
+}
+
+/// Returns the binary value of the given quaternary symbols over the given range,
+/// or None if the input is invalid.
+///
+/// Each symbol holds two bits, the symbol at `start` is the most significant one.
+///
+/// # Arguments
+/// * `symbols` - buffer containing the symbols
+/// * `start` - start symbol position (most significant)
+/// * `stop` - stop symbol position (least significant)
+pub fn get_quaternary_value(symbols: &[Option<u8>], start: usize, stop: usize) -> Option<u16> {
+    const MAX_RANGE: usize = 8;
+    if stop < start || stop - start >= MAX_RANGE {
+        return None;
+    }
+    let mut value = 0;
+    for symbol in &symbols[start..=stop] {
+        let s = (*symbol)?;
+        if s > 3 {
+            return None;
+        }
+        value = 4 * value + s as u16;
+    }
+    Some(value)
+}
+
+/// Write the given value as quaternary symbols over the given range, the inverse of
+/// `get_quaternary_value()`.
+///
+/// # Arguments
+/// * `symbols` - buffer to write the symbols to
+/// * `start` - start symbol position (most significant)
+/// * `stop` - stop symbol position (least significant)
+/// * `value` - the value to write, must fit the range
+fn put_quaternary_value(symbols: &mut [Option<u8>], start: usize, stop: usize, value: u16) {
+    for (idx, symbol) in symbols[start..=stop].iter_mut().rev().enumerate() {
+        *symbol = Some((value >> (2 * idx)) as u8 & 3);
+    }
+}
+
+/// Expand quaternary symbols into a bit buffer, most significant bit of each symbol first.
+///
+/// Invalid symbols (greater than 3) yield None bits.
+///
+/// # Arguments
+/// * `symbols` - buffer containing the symbols
+/// * `bit_buffer` - buffer to store the bits in, must be twice as long as `symbols`
+pub fn get_symbol_bits(symbols: &[Option<u8>], bit_buffer: &mut [Option<bool>]) {
+    for (idx, symbol) in symbols.iter().enumerate() {
+        let s = symbol.filter(|s| *s <= 3);
+        bit_buffer[2 * idx] = s.map(|s| s & 2 != 0);
+        bit_buffer[2 * idx + 1] = s.map(|s| s & 1 != 0);
+    }
+}
+
+/// Returns the symbol-wise majority over the given frames.
+///
+/// A symbol is None if it is absent in all frames, or if there is no single most
+/// common value.
+///
+/// # Arguments
+/// * `frames` - the frames to vote over
+pub fn vote_frames(frames: &[SymbolBuffer]) -> SymbolBuffer {
+    let mut result = [None; FRAME_SIZE];
+    for (idx, symbol) in result.iter_mut().enumerate() {
+        let mut counts = [0u8; 4];
+        for frame in frames {
+            if let Some(s) = frame[idx].filter(|s| *s <= 3) {
+                counts[s as usize] += 1;
+            }
+        }
+        let max = *counts.iter().max().unwrap();
+        if max > 0 && counts.iter().filter(|c| **c == max).count() == 1 {
+            *symbol = counts.iter().position(|c| *c == max).map(|s| s as u8);
+        }
+    }
+    result
+}
+
+/// Decode the frames of one BPC minute into the given date/time instance.
+///
+/// Each frame is checked for its frame index and parities first, parts of frames which
+/// fail these checks are left out of the symbol-wise vote over the frames.
+///
+/// Like with DCF77 and MSF, the date/time is set to the minute starting at the end of
+/// the last frame, so one minute is added to the transmitted time.
+///
+/// The date/time is first advanced by one minute using `add_minute()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(7)`
+/// * `frames` - the frames of the minute, in order of transmission
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    frames: &[SymbolBuffer; FRAMES_PER_MINUTE],
+    check_jump: bool,
+) -> DecodeReport {
+    let added_minute = rdt.add_minute();
+    let check_jump = check_jump && added_minute;
+
+    let mut time_frames = [[None; FRAME_SIZE]; FRAMES_PER_MINUTE];
+    let mut date_frames = [[None; FRAME_SIZE]; FRAMES_PER_MINUTE];
+    let mut time_votes = 0;
+    let mut date_votes = 0;
+    let mut time_parity = None;
+    let mut date_parity = None;
+    for (idx, frame) in frames.iter().enumerate() {
+        if frame[1].is_some_and(|f| f as usize != idx) {
+            continue;
+        }
+        let mut bits = [None; 2 * FRAME_SIZE];
+        get_symbol_bits(frame, &mut bits);
+        // Even parity over P1 through the high bit of P10 in the low bit of P10:
+        let parity_1 = get_parity(&bits, 2, 20, bits[21]).map(|p| !p);
+        if parity_1 == Some(true) {
+            time_frames[idx][2..=10].copy_from_slice(&frame[2..=10]);
+            // The parity bit depends on the frame index, so leave it out of the vote:
+            time_frames[idx][10] = frame[10].map(|s| s & 2);
+            time_votes += 1;
+        }
+        time_parity = time_parity.max(parity_1);
+        // Even parity over P11 through the high bit of P19 in the low bit of P19:
+        let parity_2 = get_parity(&bits, 22, 38, bits[39]).map(|p| !p);
+        if parity_2 == Some(true) {
+            date_frames[idx][11..=19].copy_from_slice(&frame[11..=19]);
+            date_frames[idx][19] = frame[19].map(|s| s & 2);
+            date_votes += 1;
+        }
+        date_parity = date_parity.max(parity_2);
+    }
+    let time = vote_frames(&time_frames);
+    let date = vote_frames(&date_frames);
+
+    // The high bits of P10 and P19 are the PM flag and the high bit of the year:
+    let pm = time[10].map(|s| s & 2 != 0);
+    let mut hour = get_quaternary_value(&time, 3, 4)
+        .zip(pm)
+        .filter(|(h, _)| *h < 12)
+        .map(|(h, pm)| h as u8 + 12 * pm as u8);
+    let mut minute = get_quaternary_value(&time, 5, 7).map(|m| m as u8);
+    let mut weekday = get_quaternary_value(&time, 8, 9).map(|wd| wd as u8);
+    let day = get_quaternary_value(&date, 11, 13).map(|d| d as u8);
+    let month = get_quaternary_value(&date, 14, 15).map(|m| m as u8);
+    let mut year = get_quaternary_value(&date, 16, 18)
+        .zip(date[19])
+        .map(|(y, s)| y as u8 + 64 * (s >> 1));
+    // Use the same century as encode_time(), which gets it from the full year:
+    let mut leap_year = rdt
+        .get_full_year_of(year, month, day, weekday)
+        .map(is_leap_year);
+    let mut day_of_year = month
+        .zip(day)
+        .zip(leap_year)
+        .and_then(|((m, d), l)| get_day_of_year(m, d, l));
+    add_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+    if hour.is_none() {
+        weekday = None;
+    } else if hour == Some(0) && minute == Some(0) {
+        weekday = weekday.map(|wd| wd % 7 + 1);
+    }
+    let month_day = day_of_year
+        .zip(leap_year)
+        .and_then(|(d, l)| get_month_day(d, l));
+    let month = month_day.map(|md| md.0);
+    let day = month_day.map(|md| md.1);
+
+    rdt.set_minute(minute, true, check_jump);
+    rdt.set_hour(hour, true, check_jump);
+    rdt.set_year(year, true, check_jump);
+    rdt.set_month(month, true, check_jump);
+    rdt.set_weekday(weekday, true, check_jump);
+    // day must be set *after* year, month, and weekday
+    rdt.set_day(day, true, check_jump);
+    // China does not observe daylight saving time:
+    rdt.set_dst(Some(false), Some(false), check_jump);
+    // BPC does not announce leap seconds, and its frames always make up 60 seconds:
+    rdt.set_leap_second(Some(false), 60);
+    rdt.bump_minutes_running();
+
+    // Missing symbols are already covered by the parity checks:
+    DecodeReport {
+        minute: FieldStatus::new(&[], time_parity, minute, rdt.get_minute()),
+        hour: FieldStatus::new(&[], time_parity, hour, rdt.get_hour()),
+        day: FieldStatus::new(&[], date_parity, day, rdt.get_day()),
+        weekday: FieldStatus::new(&[], time_parity, weekday, rdt.get_weekday()),
+        month: FieldStatus::new(&[], date_parity, month, rdt.get_month()),
+        year: FieldStatus::new(&[], date_parity, year, rdt.get_year()),
+        time_frames: time_votes,
+        date_frames: date_votes,
+    }
+}
+
+/// Encode the given date/time into the frames of one BPC minute, the inverse of
+/// `decode_time()`.
+///
+/// The transmitted minute is the one before `rdt`. Returns None if the date/time is
+/// not valid.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the frames
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<[SymbolBuffer; FRAMES_PER_MINUTE]> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let mut minute = rdt.get_minute().unwrap();
+    let mut hour = rdt.get_hour().unwrap();
+    let mut year = rdt.get_year().unwrap();
+    let mut leap_year = is_leap_year(rdt.get_full_year()?);
+    let mut day_of_year =
+        get_day_of_year(rdt.get_month().unwrap(), rdt.get_day().unwrap(), leap_year)?;
+    // Sunday is 7:
+    let mut weekday = rdt.get_weekday().unwrap();
+    if weekday == 0 {
+        weekday = 7;
+    }
+    sub_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+    if hour == 23 && minute == 59 {
+        weekday = (weekday + 5) % 7 + 1;
+    }
+    let (month, day) = get_month_day(day_of_year, leap_year)?;
+
+    let mut frames = [[None; FRAME_SIZE]; FRAMES_PER_MINUTE];
+    for (idx, frame) in frames.iter_mut().enumerate() {
+        frame[1] = Some(idx as u8);
+        frame[2] = Some(0);
+        put_quaternary_value(frame, 3, 4, (hour % 12) as u16);
+        put_quaternary_value(frame, 5, 7, minute as u16);
+        put_quaternary_value(frame, 8, 9, weekday as u16);
+        frame[10] = Some(2 * (hour >= 12) as u8);
+        put_quaternary_value(frame, 11, 13, day as u16);
+        put_quaternary_value(frame, 14, 15, month as u16);
+        put_quaternary_value(frame, 16, 18, (year % 64) as u16);
+        frame[19] = Some(2 * (year >= 64) as u8);
+        let mut bits = [None; 2 * FRAME_SIZE];
+        get_symbol_bits(frame, &mut bits);
+        // Even parities in the low bits of P10 and P19:
+        frame[10] = frame[10]
+            .zip(get_parity(&bits, 2, 20, Some(false)))
+            .map(|(s, p)| s + p as u8);
+        frame[19] = frame[19]
+            .zip(get_parity(&bits, 22, 38, Some(false)))
+            .map(|(s, p)| s + p as u8);
+    }
+    Some(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rdt_at;
+
+    // Thursday 2024-02-29 15:34 CST: P3-P4 hour 3 and P10 PM, P5-P7 minute 34,
+    // P8-P9 Thursday, P11-P13 day 29, P14-P15 month 2, P16-P18 year 24, and the even
+    // parities over P1-P10 and P11-P19 in the low bits of P10 and P19
+    const FRAME_REGULAR: SymbolBuffer = [
+        None,
+        Some(0),
+        Some(0),
+        Some(0),
+        Some(3),
+        Some(2),
+        Some(0),
+        Some(2),
+        Some(1),
+        Some(0),
+        Some(2),
+        Some(1),
+        Some(3),
+        Some(1),
+        Some(0),
+        Some(2),
+        Some(1),
+        Some(2),
+        Some(0),
+        Some(1),
+    ];
+
+    // Sunday 2024-03-31 23:59 CST: hour 11 PM, minute 59, Sunday (7), day 31, month 3,
+    // year 24
+    const FRAME_NEW_MONTH: SymbolBuffer = [
+        None,
+        Some(0),
+        Some(0),
+        Some(2),
+        Some(3),
+        Some(3),
+        Some(2),
+        Some(3),
+        Some(1),
+        Some(3),
+        Some(2),
+        Some(1),
+        Some(3),
+        Some(3),
+        Some(0),
+        Some(3),
+        Some(1),
+        Some(2),
+        Some(0),
+        Some(1),
+    ];
+
+    /// Build the frames of a minute from frame 0. Frames 1 and 2 have a single set bit
+    /// in their frame index P1, which flips the parity bit in P10.
+    fn frames(frame: SymbolBuffer) -> [SymbolBuffer; FRAMES_PER_MINUTE] {
+        let mut frames = [frame; FRAMES_PER_MINUTE];
+        for (idx, frame) in frames.iter_mut().enumerate().skip(1) {
+            frame[1] = Some(idx as u8);
+            frame[10] = frame[10].map(|s| s ^ 1);
+        }
+        frames
+    }
+
+    #[test]
+    fn test_get_quaternary_value_regular() {
+        assert_eq!(
+            get_quaternary_value(&[Some(1), Some(3), Some(2)], 0, 2),
+            Some(0b01_11_10)
+        );
+    }
+    #[test]
+    fn bad_get_quaternary_value_too_large_symbol() {
+        assert_eq!(get_quaternary_value(&[Some(1), Some(4)], 0, 1), None);
+    }
+    #[test]
+    fn bad_get_quaternary_value_none() {
+        assert_eq!(get_quaternary_value(&[Some(1), None], 0, 1), None);
+    }
+    #[test]
+    fn bad_get_quaternary_value_backwards() {
+        assert_eq!(get_quaternary_value(&[Some(1), Some(2)], 1, 0), None);
+    }
+
+    #[test]
+    fn test_get_symbol_bits() {
+        let mut bits = [None; 6];
+        get_symbol_bits(&[Some(2), None, Some(5)], &mut bits);
+        assert_eq!(bits, [Some(true), Some(false), None, None, None, None]);
+    }
+
+    #[test]
+    fn test_vote_frames_majority() {
+        let mut frames = [[Some(1); FRAME_SIZE]; 3];
+        frames[0][4] = Some(2);
+        frames[1][5] = None;
+        frames[2][5] = Some(3);
+        let result = vote_frames(&frames);
+        assert_eq!(result[4], Some(1));
+        assert_eq!(result[5], None); // tie
+        assert_eq!(result[6], Some(1));
+    }
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        // Thursday 2024-02-29 15:34 CST, so 15:35 at the end of the minute
+        let report = decode_time(&mut rdt, &frames(FRAME_REGULAR), true);
+        assert_eq!(
+            report,
+            DecodeReport {
+                minute: FieldStatus::Ok,
+                hour: FieldStatus::Ok,
+                day: FieldStatus::Ok,
+                weekday: FieldStatus::Ok,
+                month: FieldStatus::Ok,
+                year: FieldStatus::Ok,
+                time_frames: 3,
+                date_frames: 3,
+            }
+        );
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert_eq!(rdt.get_hour(), Some(15));
+        assert_eq!(rdt.get_day(), Some(29));
+        assert_eq!(rdt.get_month(), Some(2));
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert!(rdt.is_valid());
+    }
+    #[test]
+    fn test_decode_time_new_month() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        // Sunday 2024-03-31 23:59
+        decode_time(&mut rdt, &frames(FRAME_NEW_MONTH), true);
+        assert_eq!(rdt.get_minute(), Some(0));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_day(), Some(1));
+        assert_eq!(rdt.get_month(), Some(4));
+        assert_eq!(rdt.get_weekday(), Some(1));
+    }
+    #[test]
+    fn test_decode_time_one_corrupt_frame() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frames(FRAME_REGULAR);
+        // flip a minute bit, the parity catches it
+        buffer[1][7] = Some(buffer[1][7].unwrap() ^ 1);
+        let report = decode_time(&mut rdt, &buffer, true);
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(report.time_frames, 2);
+        assert_eq!(report.date_frames, 3);
+        assert_eq!(rdt.get_minute(), Some(35));
+    }
+    #[test]
+    fn test_decode_time_two_missing_frames() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frames(FRAME_REGULAR);
+        buffer[0][12] = None;
+        buffer[2] = [None; FRAME_SIZE];
+        let report = decode_time(&mut rdt, &buffer, true);
+        assert_eq!(report.date_frames, 1);
+        assert_eq!(report.day, FieldStatus::Ok);
+        assert_eq!(rdt.get_day(), Some(29));
+    }
+    #[test]
+    fn test_decode_time_all_bad_parity() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frames(FRAME_REGULAR);
+        for frame in &mut buffer {
+            frame[10] = Some(frame[10].unwrap() ^ 1);
+        }
+        let report = decode_time(&mut rdt, &buffer, true);
+        assert_eq!(report.minute, FieldStatus::BadParity);
+        assert_eq!(report.time_frames, 0);
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn test_decode_time_wrong_frame_index() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frames(FRAME_REGULAR);
+        buffer[0][1] = Some(2);
+        let report = decode_time(&mut rdt, &buffer, true);
+        assert_eq!(report.time_frames, 2);
+        assert_eq!(report.minute, FieldStatus::Ok);
+    }
+
+    #[test]
+    fn test_put_quaternary_value() {
+        let mut symbols = [None; 3];
+        put_quaternary_value(&mut symbols, 0, 2, 0b01_11_10);
+        assert_eq!(symbols, [Some(1), Some(3), Some(2)]);
+    }
+
+    #[test]
+    fn test_encode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        decode_time(&mut rdt, &frames(FRAME_REGULAR), true);
+        assert_eq!(encode_time(&rdt), Some(frames(FRAME_REGULAR)));
+    }
+    #[test]
+    fn test_encode_time_round_trip() {
+        // Thursday 2099-12-31 12:00 CST
+        let mut rdt = rdt_at(7, (99, 12, 31), (12, 0), false);
+        for _ in 0..(24 * 60) {
+            let mut next_rdt = rdt;
+            assert!(next_rdt.add_minute());
+            let report = decode_time(&mut rdt, &encode_time(&next_rdt).unwrap(), true);
+            assert_eq!((report.time_frames, report.date_frames), (3, 3));
+            assert!(!rdt.get_jump_minute() && !rdt.get_jump_day() && !rdt.get_jump_weekday());
+        }
+        assert_eq!(rdt.get_year(), Some(0));
+        assert_eq!(rdt.get_month(), Some(1));
+        assert_eq!(rdt.get_day(), Some(1));
+        assert_eq!(rdt.get_weekday(), Some(5));
+        assert_eq!(rdt.get_hour(), Some(12));
+    }
+    #[test]
+    fn test_encode_time_century() {
+        // Monday 2100-03-01 00:00 CST, 2100 is not a leap year
+        let mut rdt = rdt_at(7, (0, 3, 1), (0, 0), false);
+        rdt.set_pivot_year(Some(2050));
+        // rdt_at() takes the day of the week from 2000-03-01:
+        rdt.set_weekday(Some(1), true, false);
+        let frames = encode_time(&rdt).unwrap();
+        assert_eq!(get_quaternary_value(&frames[0], 11, 15), Some(28 * 16 + 2));
+        let mut decoded = RadioDateTimeUtils::new(7);
+        decoded.set_pivot_year(Some(2050));
+        decode_time(&mut decoded, &frames, false);
+        assert_eq!(decoded.get_month(), Some(3));
+        assert_eq!(decoded.get_day(), Some(1));
+        assert_eq!(decoded.get_weekday(), Some(1));
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(7)), None);
+    }
+}
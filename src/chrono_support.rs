@@ -0,0 +1,172 @@
+//! Conversions between `RadioDateTimeUtils` and the date/time types of the `chrono` crate.
+
+use crate::utc::{from_local_minutes, from_utc_minutes, is_summer, StationDateTime};
+use crate::{ConversionError, RadioDateTimeUtils, Station};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+/// Converts into the local time of the station, at the start of the minute.
+impl TryFrom<&RadioDateTimeUtils> for NaiveDateTime {
+    type Error = ConversionError;
+
+    fn try_from(rdt: &RadioDateTimeUtils) -> Result<Self, Self::Error> {
+        if !rdt.is_valid() {
+            return Err(ConversionError);
+        }
+        let date = NaiveDate::from_ymd_opt(
+            rdt.get_full_year().ok_or(ConversionError)? as i32,
+            rdt.get_month().unwrap() as u32,
+            rdt.get_day().unwrap() as u32,
+        )
+        .ok_or(ConversionError)?;
+        let time = NaiveTime::from_hms_opt(
+            rdt.get_hour().unwrap() as u32,
+            rdt.get_minute().unwrap() as u32,
+            0,
+        )
+        .ok_or(ConversionError)?;
+        Ok(Self::new(date, time))
+    }
+}
+
+/// Converts into the local time of the station, with its offset to UTC determined by the
+/// station and the DST bitmask.
+impl TryFrom<StationDateTime<'_>> for DateTime<FixedOffset> {
+    type Error = ConversionError;
+
+    fn try_from(value: StationDateTime) -> Result<Self, Self::Error> {
+        let StationDateTime { rdt, station } = value;
+        let local = NaiveDateTime::try_from(rdt)?;
+        let summer = is_summer(rdt.get_dst().unwrap(), rdt.get_minute().unwrap());
+        FixedOffset::east_opt(station.get_utc_offset(summer) as i32 * 60)
+            .ok_or(ConversionError)?
+            .from_local_datetime(&local)
+            .single()
+            .ok_or(ConversionError)
+    }
+}
+
+/// Converts from a date/time with any offset into the local time of the station, with
+/// daylight saving time determined by `utc::is_summer_at()`. Seconds are discarded.
+impl TryFrom<(DateTime<FixedOffset>, Station)> for RadioDateTimeUtils {
+    type Error = ConversionError;
+
+    fn try_from(value: (DateTime<FixedOffset>, Station)) -> Result<Self, Self::Error> {
+        let (value, station) = value;
+        from_utc_minutes(station, value.timestamp().div_euclid(60))
+    }
+}
+
+/// Converts from the local time of the station, with daylight saving time determined by
+/// `utc::is_summer_at()`. Seconds are discarded.
+///
+/// Fails for a local time which does not exist or is ambiguous because of a DST change.
+impl TryFrom<(NaiveDateTime, Station)> for RadioDateTimeUtils {
+    type Error = ConversionError;
+
+    fn try_from(value: (NaiveDateTime, Station)) -> Result<Self, Self::Error> {
+        let (value, station) = value;
+        from_local_minutes(station, value.and_utc().timestamp().div_euclid(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DST_ANNOUNCED, DST_SUMMER};
+
+    fn naive(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_naive_date_time() {
+        let value = naive(2024, 2, 29, 23, 59);
+        let rdt = RadioDateTimeUtils::try_from((value, Station::Dcf77)).unwrap();
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(NaiveDateTime::try_from(&rdt), Ok(value));
+        let rdt = RadioDateTimeUtils::try_from((naive(2024, 7, 4, 12, 0), Station::Dcf77));
+        assert_eq!(rdt.unwrap().get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn test_naive_date_time_sunday() {
+        let value = naive(2024, 3, 3, 12, 0);
+        let rdt = RadioDateTimeUtils::try_from((value, Station::Dcf77)).unwrap();
+        assert_eq!(rdt.get_weekday(), Some(7));
+        let rdt = RadioDateTimeUtils::try_from((value, Station::Msf)).unwrap();
+        assert_eq!(rdt.get_weekday(), Some(0));
+    }
+    #[test]
+    fn bad_naive_date_time_dst_change() {
+        // 02:30 does not exist on 2024-03-31 and occurs twice on 2024-10-27
+        for value in [naive(2024, 3, 31, 2, 30), naive(2024, 10, 27, 2, 30)] {
+            assert_eq!(
+                RadioDateTimeUtils::try_from((value, Station::Dcf77)).err(),
+                Some(ConversionError)
+            );
+        }
+        // but only the local time of the station counts:
+        assert!(RadioDateTimeUtils::try_from((naive(2024, 3, 31, 2, 30), Station::Jjy)).is_ok());
+    }
+    #[test]
+    fn bad_naive_date_time_century() {
+        assert_eq!(
+            RadioDateTimeUtils::try_from((naive(1899, 12, 31, 12, 0), Station::Msf)).err(),
+            Some(ConversionError)
+        );
+        assert_eq!(
+            RadioDateTimeUtils::try_from((naive(2300, 1, 1, 12, 0), Station::Msf)).err(),
+            Some(ConversionError)
+        );
+    }
+    #[test]
+    fn test_date_time_fixed_offset() {
+        // 2024-07-04 12:00 UTC
+        let value = FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2024, 7, 4, 12, 0, 0)
+            .unwrap();
+        let rdt = RadioDateTimeUtils::try_from((value, Station::Dcf77)).unwrap();
+        assert_eq!(rdt.get_hour(), Some(14));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+        let cest =
+            DateTime::<FixedOffset>::try_from(StationDateTime::new(&rdt, Station::Dcf77)).unwrap();
+        assert_eq!(cest.offset().local_minus_utc(), 7200);
+        assert_eq!(cest, value);
+
+        let rdt = RadioDateTimeUtils::try_from((cest, Station::Jjy)).unwrap();
+        assert_eq!(rdt.get_hour(), Some(21));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(rdt.get_weekday(), Some(4));
+    }
+    #[test]
+    fn test_date_time_dst_pending() {
+        // 02:00 CET reached using add_minute() from 02:59 CEST
+        let value = FixedOffset::east_opt(7200)
+            .unwrap()
+            .with_ymd_and_hms(2024, 10, 27, 2, 59, 0)
+            .unwrap();
+        let mut rdt = RadioDateTimeUtils::try_from((value, Station::Dcf77)).unwrap();
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+        rdt.set_dst(Some(true), Some(true), false);
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_ANNOUNCED));
+        assert!(rdt.add_minute());
+        let cet =
+            DateTime::<FixedOffset>::try_from(StationDateTime::new(&rdt, Station::Dcf77)).unwrap();
+        assert_eq!(cet.offset().local_minus_utc(), 3600);
+        assert_eq!(cet.timestamp(), 1_729_990_800);
+    }
+    #[test]
+    fn bad_naive_date_time() {
+        let rdt = RadioDateTimeUtils::new(7);
+        assert_eq!(NaiveDateTime::try_from(&rdt), Err(ConversionError));
+        assert_eq!(
+            DateTime::<FixedOffset>::try_from(StationDateTime::new(&rdt, Station::Wwvb)),
+            Err(ConversionError)
+        );
+    }
+}
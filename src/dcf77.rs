@@ -0,0 +1,351 @@
+//! Decoder for the amplitude-modulated time code of DCF77 (Mainflingen, Germany, 77.5 kHz).
+
+use crate::radio_datetime_helpers::{get_bcd_value, get_parity, set_bcd_value, set_parity};
+use crate::utc::{get_minute_length, is_summer};
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, DST_ANNOUNCED, DST_PROCESSED, DST_SUMMER,
+    LEAP_ANNOUNCED, LEAP_PROCESSED,
+};
+
+/// Per-field outcome of decoding one DCF77 minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub dst: FieldStatus,
+    pub leap_second: FieldStatus,
+}
+
+/// Decode the bit buffer of one DCF77 minute into the given date/time instance.
+///
+/// The date/time is first advanced by one minute using `add_minute()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(7)`
+/// * `bit_buffer` - the bits of the minute, indexed by second
+/// * `minute_length` - the length of the decoded minute in seconds, 60 or 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    let added_minute = rdt.add_minute();
+    let check_jump = check_jump && added_minute;
+    // Bit 0 is always 0 and bit 20 (start of encoded time) is always 1:
+    let frame_ok = (60..=61).contains(&minute_length)
+        && bit_buffer[0] == Some(false)
+        && bit_buffer[20] == Some(true);
+
+    let parity_1 = get_parity(bit_buffer, 21, 27, bit_buffer[28]).map(|p| !p);
+    let minute = get_bcd_value(bit_buffer, 21, 27).filter(|_| frame_ok);
+    rdt.set_minute(minute, parity_1 == Some(true), check_jump);
+
+    let parity_2 = get_parity(bit_buffer, 29, 34, bit_buffer[35]).map(|p| !p);
+    let hour = get_bcd_value(bit_buffer, 29, 34).filter(|_| frame_ok);
+    rdt.set_hour(hour, parity_2 == Some(true), check_jump);
+
+    let parity_3 = get_parity(bit_buffer, 36, 57, bit_buffer[58]).map(|p| !p);
+    let year = get_bcd_value(bit_buffer, 50, 57).filter(|_| frame_ok);
+    rdt.set_year(year, parity_3 == Some(true), check_jump);
+    let month = get_bcd_value(bit_buffer, 45, 49).filter(|_| frame_ok);
+    rdt.set_month(month, parity_3 == Some(true), check_jump);
+    let weekday = get_bcd_value(bit_buffer, 42, 44).filter(|_| frame_ok);
+    rdt.set_weekday(weekday, parity_3 == Some(true), check_jump);
+    // day must be set *after* year, month, and weekday
+    let day = get_bcd_value(bit_buffer, 36, 41).filter(|_| frame_ok);
+    rdt.set_day(day, parity_3 == Some(true), check_jump);
+
+    // Bits 17 (CEST) and 18 (CET) must differ:
+    let dst = if bit_buffer[17].is_some() && bit_buffer[17] != bit_buffer[18] {
+        bit_buffer[17]
+    } else {
+        None
+    };
+    rdt.set_dst(dst, bit_buffer[16], check_jump);
+    // DCF77 only announces positive leap seconds:
+    rdt.set_leap_second(
+        bit_buffer[19].filter(|_| minute_length != 59),
+        minute_length,
+    );
+    rdt.bump_minutes_running();
+
+    DecodeReport {
+        minute: FieldStatus::new(&bit_buffer[21..=27], parity_1, minute, rdt.get_minute()),
+        hour: FieldStatus::new(&bit_buffer[29..=34], parity_2, hour, rdt.get_hour()),
+        day: FieldStatus::new(&bit_buffer[36..=41], parity_3, day, rdt.get_day()),
+        weekday: FieldStatus::new(&bit_buffer[42..=44], parity_3, weekday, rdt.get_weekday()),
+        month: FieldStatus::new(&bit_buffer[45..=49], parity_3, month, rdt.get_month()),
+        year: FieldStatus::new(&bit_buffer[50..=57], parity_3, year, rdt.get_year()),
+        dst: FieldStatus::new(
+            &bit_buffer[16..=18],
+            Some(true),
+            dst.map(|d| d as u8),
+            rdt.get_dst().map(|d| (d & DST_SUMMER != 0) as u8),
+        ),
+        leap_second: FieldStatus::new(
+            &bit_buffer[19..=19],
+            Some(true),
+            Some(minute_length).filter(|m| (60..=61).contains(m)),
+            Some(minute_length),
+        ),
+    }
+}
+
+/// Encode the given date/time into the bit buffer of one DCF77 minute, the inverse of
+/// `decode_time()`.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid. The minute marker and any unused bits at the end are None.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<bool>; BIT_BUFFER_SIZE], u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let minute = rdt.get_minute().unwrap();
+    let dst = rdt.get_dst().unwrap();
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    // DCF77 does not transmit the sign of a leap second, only encode positive ones:
+    let minute_length = 60 + (get_minute_length(leap_second, minute) == 61) as u8;
+    let mut bit_buffer = [None; BIT_BUFFER_SIZE];
+    // Bit 0 is always 0, bits 1 through 15 (third-party data and call bit) are left 0:
+    for bit in &mut bit_buffer[0..minute_length as usize - 1] {
+        *bit = Some(false);
+    }
+    // Announcements last until the end of the minute before the change:
+    bit_buffer[16] = Some(dst & DST_ANNOUNCED != 0 || minute == 0 && dst & DST_PROCESSED != 0);
+    let summer = is_summer(dst, minute);
+    bit_buffer[17] = Some(summer);
+    bit_buffer[18] = Some(!summer);
+    bit_buffer[19] =
+        Some(leap_second & LEAP_ANNOUNCED != 0 || minute == 0 && leap_second & LEAP_PROCESSED != 0);
+    bit_buffer[20] = Some(true);
+    set_bcd_value(&mut bit_buffer, 21, 27, minute);
+    set_parity(&mut bit_buffer, 21, 27, 28, false);
+    set_bcd_value(&mut bit_buffer, 29, 34, rdt.get_hour().unwrap());
+    set_parity(&mut bit_buffer, 29, 34, 35, false);
+    set_bcd_value(&mut bit_buffer, 36, 41, rdt.get_day().unwrap());
+    // Sunday is 7:
+    let weekday = rdt.get_weekday().unwrap();
+    set_bcd_value(
+        &mut bit_buffer,
+        42,
+        44,
+        if weekday == 0 { 7 } else { weekday },
+    );
+    set_bcd_value(&mut bit_buffer, 45, 49, rdt.get_month().unwrap());
+    set_bcd_value(&mut bit_buffer, 50, 57, rdt.get_year().unwrap());
+    set_parity(&mut bit_buffer, 36, 57, 58, false);
+    Some((bit_buffer, minute_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DST_ANNOUNCED, LEAP_ANNOUNCED};
+
+    // Thursday 2024-01-25 22:34 CET, no announcements
+    fn frame() -> [Option<bool>; BIT_BUFFER_SIZE] {
+        let mut buffer = [Some(false); BIT_BUFFER_SIZE];
+        buffer[18] = Some(true);
+        buffer[20] = Some(true);
+        set_bcd_value(&mut buffer, 21, 27, 34);
+        set_parity(&mut buffer, 21, 27, 28, false);
+        set_bcd_value(&mut buffer, 29, 34, 22);
+        set_parity(&mut buffer, 29, 34, 35, false);
+        set_bcd_value(&mut buffer, 36, 41, 25);
+        set_bcd_value(&mut buffer, 42, 44, 4);
+        set_bcd_value(&mut buffer, 45, 49, 1);
+        set_bcd_value(&mut buffer, 50, 57, 24);
+        set_parity(&mut buffer, 36, 57, 58, false);
+        buffer[59] = None;
+        buffer[60] = None;
+        buffer[61] = None;
+        buffer
+    }
+
+    const ALL_OK: DecodeReport = DecodeReport {
+        minute: FieldStatus::Ok,
+        hour: FieldStatus::Ok,
+        day: FieldStatus::Ok,
+        weekday: FieldStatus::Ok,
+        month: FieldStatus::Ok,
+        year: FieldStatus::Ok,
+        dst: FieldStatus::Ok,
+        leap_second: FieldStatus::Ok,
+    };
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        assert_eq!(decode_time(&mut rdt, &frame(), 60, true), ALL_OK);
+        assert_eq!(rdt.get_minute(), Some(34));
+        assert_eq!(rdt.get_hour(), Some(22));
+        assert_eq!(rdt.get_day(), Some(25));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_month(), Some(1));
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(rdt.get_leap_second(), Some(0));
+        assert!(rdt.is_valid());
+    }
+    #[test]
+    fn continue_decode_time_next_minute() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        decode_time(&mut rdt, &frame(), 60, true);
+        let mut buffer = frame();
+        set_bcd_value(&mut buffer, 21, 27, 35);
+        set_parity(&mut buffer, 21, 27, 28, false);
+        assert_eq!(decode_time(&mut rdt, &buffer, 60, true), ALL_OK);
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert!(!rdt.get_jump_minute());
+    }
+    #[test]
+    fn continue_decode_time_jump() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        decode_time(&mut rdt, &frame(), 60, true);
+        // same minute again, so it jumped compared to add_minute()
+        assert_eq!(decode_time(&mut rdt, &frame(), 60, true), ALL_OK);
+        assert_eq!(rdt.get_minute(), Some(34));
+        assert!(rdt.get_jump_minute());
+        assert!(!rdt.get_jump_hour());
+    }
+    #[test]
+    fn test_decode_time_bad_parity_minute() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[28] = Some(!buffer[28].unwrap());
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.minute, FieldStatus::BadParity);
+        assert_eq!(report.hour, FieldStatus::Ok);
+        assert_eq!(rdt.get_minute(), None);
+        assert_eq!(rdt.get_hour(), Some(22));
+    }
+    #[test]
+    fn test_decode_time_missing_date_bit() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[46] = None;
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.month, FieldStatus::Missing);
+        assert_eq!(report.day, FieldStatus::Missing); // shares the date parity
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(rdt.get_month(), None);
+    }
+    #[test]
+    fn test_decode_time_bad_nibble() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        // minute units 0b1111, adjust parity accordingly
+        for bit in &mut buffer[21..=24] {
+            *bit = Some(true);
+        }
+        set_parity(&mut buffer, 21, 27, 28, false);
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.minute, FieldStatus::Invalid);
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn test_decode_time_bad_frame() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[20] = Some(false);
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.minute, FieldStatus::Invalid);
+        assert_eq!(report.year, FieldStatus::Invalid);
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn test_decode_time_bad_dst() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[17] = Some(true); // both CEST and CET
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.dst, FieldStatus::Invalid);
+        assert_eq!(rdt.get_dst(), None);
+    }
+    #[test]
+    fn test_decode_time_announcements() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[16] = Some(true);
+        buffer[19] = Some(true);
+        decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(rdt.get_dst(), Some(DST_ANNOUNCED));
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+    }
+    #[test]
+    fn test_decode_time_bad_minute_length() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let report = decode_time(&mut rdt, &frame(), 59, false);
+        assert_eq!(report.leap_second, FieldStatus::Invalid);
+        assert_eq!(report.minute, FieldStatus::Invalid);
+        assert_eq!(rdt.get_leap_second(), None);
+    }
+
+    #[test]
+    fn test_encode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        decode_time(&mut rdt, &frame(), 60, false);
+        assert_eq!(encode_time(&rdt), Some((frame(), 60)));
+    }
+    #[test]
+    fn test_encode_time_round_trip() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        decode_time(&mut rdt, &frame(), 60, false);
+        let mut buffer = frame();
+        for _ in 0..90 {
+            let mut next_rdt = rdt;
+            assert!(next_rdt.add_minute());
+            let (next, minute_length) = encode_time(&next_rdt).unwrap();
+            assert_eq!(decode_time(&mut rdt, &next, minute_length, true), ALL_OK);
+            assert!(!rdt.get_jump_minute() && !rdt.get_jump_hour() && !rdt.get_jump_day());
+            buffer = next;
+        }
+        // Friday 2024-01-26 00:04 CET
+        assert_eq!(get_bcd_value(&buffer, 42, 44), Some(5));
+        assert_eq!(rdt.get_day(), Some(26));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_minute(), Some(4));
+    }
+    #[test]
+    fn test_encode_time_announcements() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[16] = Some(true);
+        buffer[19] = Some(true);
+        decode_time(&mut rdt, &buffer, 60, false);
+        let (encoded, minute_length) = encode_time(&rdt).unwrap();
+        assert_eq!(encoded, buffer);
+        assert_eq!(minute_length, 60);
+    }
+    #[test]
+    fn test_encode_time_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let mut buffer = frame();
+        buffer[19] = Some(true);
+        decode_time(&mut rdt, &buffer, 60, false);
+        rdt.set_minute(Some(59), true, false);
+        let mut next_rdt = rdt;
+        assert!(next_rdt.add_minute());
+        let (encoded, minute_length) = encode_time(&next_rdt).unwrap();
+        assert_eq!(minute_length, 61);
+        assert_eq!(encoded[19], Some(true));
+        assert_eq!(encoded[59], Some(false));
+        assert_eq!(encoded[60], None);
+        decode_time(&mut rdt, &encoded, minute_length, false);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(7)), None);
+    }
+}
@@ -0,0 +1,231 @@
+//! Decoder for the phase-modulated time code of DCF77.
+//!
+//! Besides the amplitude-modulated pulses, DCF77 transmits each bit of the time code as
+//! a pseudo-random noise (PRN) sequence of 512 chips, phase-modulating the carrier
+//! starting 200 ms after the start of the second. The sequence is sent as is for a 0
+//! bit and inverted for a 1 bit.
+//!
+//! The functions here work on phase samples taken once per chip, with positive values
+//! for a positive phase deviation. Correlating these samples against the PRN sequence
+//! recovers the bits, which are collected into the same bit buffer as the one used by
+//! `dcf77::decode_time()`.
+
+use crate::BIT_BUFFER_SIZE;
+
+/// Number of chips of the PRN sequence in each second.
+pub const CHIPS: usize = 512;
+
+/// Returns the PRN sequence, with true for a chip with a negative phase deviation.
+///
+/// The sequence is generated by a 9-bit linear feedback shift register with the
+/// polynomial x^9 + x^5 + 1, started with all ones. Its 511 chips are followed by a
+/// single 0 chip to obtain 512 chips.
+pub fn get_prn_sequence() -> [bool; CHIPS] {
+    let mut sequence = [false; CHIPS];
+    let mut register: u16 = 0x1ff;
+    for chip in sequence.iter_mut().take(CHIPS - 1) {
+        *chip = register & 1 == 1;
+        let feedback = (register ^ (register >> 4)) & 1;
+        register = (register >> 1) | (feedback << 8);
+    }
+    sequence
+}
+
+/// Returns the correlation of the samples starting at `offset` with the PRN sequence,
+/// or None if there are not enough samples.
+///
+/// The result is positive for a 0 bit and negative for a 1 bit.
+///
+/// # Arguments
+/// * `prn` - the PRN sequence, as obtained from `get_prn_sequence()`
+/// * `samples` - the phase samples, one per chip
+/// * `offset` - the index of the sample of the first chip
+pub fn get_correlation(prn: &[bool; CHIPS], samples: &[i16], offset: usize) -> Option<i32> {
+    let samples = samples.get(offset..offset + CHIPS)?;
+    Some(
+        samples
+            .iter()
+            .zip(prn)
+            .map(|(s, chip)| if *chip { -(*s as i32) } else { *s as i32 })
+            .sum(),
+    )
+}
+
+/// Returns the offset of the sample where the PRN sequence starts together with the
+/// correlation at that offset, or None if there are fewer than `CHIPS` samples.
+///
+/// The offset with the strongest correlation (of either sign) wins, it gives the
+/// start of the sequence with an accuracy of one chip.
+///
+/// # Arguments
+/// * `prn` - the PRN sequence, as obtained from `get_prn_sequence()`
+/// * `samples` - the phase samples, one per chip
+pub fn find_start(prn: &[bool; CHIPS], samples: &[i16]) -> Option<(usize, i32)> {
+    let mut best: Option<(usize, i32)> = None;
+    for offset in 0..=samples.len().checked_sub(CHIPS)? {
+        let correlation = get_correlation(prn, samples, offset)?;
+        if best.is_none_or(|(_, c)| correlation.unsigned_abs() > c.unsigned_abs()) {
+            best = Some((offset, correlation));
+        }
+    }
+    best
+}
+
+/// Returns the bit transmitted by the samples starting at `offset`, or None if there are
+/// not enough samples or if the correlation is too weak.
+///
+/// The correlation is too weak if it is less than half of the sum of the magnitudes of
+/// the samples, i.e. if fewer than 75% of the chips agree with the sequence or its
+/// inverse for samples of equal magnitude.
+///
+/// # Arguments
+/// * `prn` - the PRN sequence, as obtained from `get_prn_sequence()`
+/// * `samples` - the phase samples, one per chip
+/// * `offset` - the index of the sample of the first chip
+pub fn get_bit(prn: &[bool; CHIPS], samples: &[i16], offset: usize) -> Option<bool> {
+    let correlation = get_correlation(prn, samples, offset)?;
+    let magnitude: u32 = samples[offset..offset + CHIPS]
+        .iter()
+        .map(|s| s.unsigned_abs() as u32)
+        .sum();
+    if magnitude == 0 || 2 * correlation.unsigned_abs() < magnitude {
+        None
+    } else {
+        Some(correlation < 0)
+    }
+}
+
+/// Collects the bits of one minute recovered from the phase modulation.
+#[derive(Clone, Copy)]
+pub struct PmDecoder {
+    prn: [bool; CHIPS],
+    bit_buffer: [Option<bool>; BIT_BUFFER_SIZE],
+}
+
+impl Default for PmDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PmDecoder {
+    /// Initialize a new PmDecoder instance with an empty bit buffer.
+    pub fn new() -> Self {
+        Self {
+            prn: get_prn_sequence(),
+            bit_buffer: [None; BIT_BUFFER_SIZE],
+        }
+    }
+
+    /// Get the bits received so far, indexed by second, for use by `dcf77::decode_time()`.
+    pub fn get_bit_buffer(&self) -> &[Option<bool>; BIT_BUFFER_SIZE] {
+        &self.bit_buffer
+    }
+
+    /// Clear the bit buffer, to be called at the start of each minute.
+    pub fn clear(&mut self) {
+        self.bit_buffer = [None; BIT_BUFFER_SIZE];
+    }
+
+    /// Recover the bit of the given second from its phase samples and store it.
+    ///
+    /// The samples must contain the whole sequence, its start is searched for. Returns
+    /// the offset of the start of the sequence, or None if no bit could be recovered.
+    ///
+    /// # Arguments
+    /// * `second` - the second within the minute
+    /// * `samples` - the phase samples of this second, one per chip
+    pub fn handle_second(&mut self, second: u8, samples: &[i16]) -> Option<usize> {
+        let idx = second as usize;
+        if idx >= BIT_BUFFER_SIZE {
+            return None;
+        }
+        let (offset, _) = find_start(&self.prn, samples)?;
+        self.bit_buffer[idx] = get_bit(&self.prn, samples, offset);
+        self.bit_buffer[idx].map(|_| offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RadioDateTimeUtils, DST_SUMMER};
+
+    /// Phase samples for one bit with `lead` samples of noise before the sequence.
+    fn modulate(prn: &[bool; CHIPS], bit: bool, lead: usize, noise: u32) -> [i16; CHIPS + 40] {
+        let mut samples = [0; CHIPS + 40];
+        let mut seed = noise;
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            // small deterministic noise
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let jitter = ((seed >> 16) % 41) as i16 - 20;
+            *sample = if (lead..lead + CHIPS).contains(&idx) {
+                if prn[idx - lead] != bit {
+                    -100
+                } else {
+                    100
+                }
+            } else {
+                0
+            } + jitter;
+        }
+        samples
+    }
+
+    #[test]
+    fn test_prn_sequence() {
+        let prn = get_prn_sequence();
+        // a maximum-length sequence has one more 1 than 0:
+        assert_eq!(prn[..511].iter().filter(|c| **c).count(), 256);
+        assert!(!prn[511]);
+        assert_eq!(prn[..9], [true; 9]);
+    }
+    #[test]
+    fn test_find_start() {
+        let prn = get_prn_sequence();
+        let samples = modulate(&prn, false, 17, 1);
+        let (offset, correlation) = find_start(&prn, &samples).unwrap();
+        assert_eq!(offset, 17);
+        assert!(correlation > 40_000);
+        assert_eq!(find_start(&prn, &samples[..CHIPS - 1]), None);
+    }
+    #[test]
+    fn test_get_bit() {
+        let prn = get_prn_sequence();
+        assert_eq!(get_bit(&prn, &modulate(&prn, false, 3, 2), 3), Some(false));
+        assert_eq!(get_bit(&prn, &modulate(&prn, true, 3, 3), 3), Some(true));
+        // misaligned:
+        assert_eq!(get_bit(&prn, &modulate(&prn, true, 3, 4), 10), None);
+        assert_eq!(get_bit(&prn, &[0; CHIPS], 0), None);
+        assert_eq!(get_bit(&prn, &[0; CHIPS], 1), None);
+    }
+    #[test]
+    fn test_decode_minute() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_year(Some(24), true, false);
+        rdt.set_month(Some(6), true, false);
+        rdt.set_weekday(Some(7), true, false);
+        rdt.set_day(Some(16), true, false);
+        rdt.set_hour(Some(12), true, false);
+        rdt.set_minute(Some(34), true, false);
+        rdt.set_dst(Some(true), Some(false), false);
+        rdt.set_leap_second(Some(false), 60);
+        let (bits, minute_length) = crate::dcf77::encode_time(&rdt).unwrap();
+
+        let mut decoder = PmDecoder::new();
+        let prn = get_prn_sequence();
+        for second in 0..minute_length - 1 {
+            let samples = modulate(&prn, bits[second as usize].unwrap(), 20, second as u32);
+            assert_eq!(decoder.handle_second(second, &samples), Some(20));
+        }
+        assert_eq!(decoder.get_bit_buffer()[..59], bits[..59]);
+        let mut decoded = RadioDateTimeUtils::new(7);
+        crate::dcf77::decode_time(&mut decoded, decoder.get_bit_buffer(), minute_length, false);
+        assert_eq!(decoded.get_minute(), Some(34));
+        assert_eq!(decoded.get_hour(), Some(12));
+        assert_eq!(decoded.get_day(), Some(16));
+        assert_eq!(decoded.get_dst(), Some(DST_SUMMER));
+        decoder.clear();
+        assert_eq!(decoder.get_bit_buffer()[0], None);
+    }
+}
@@ -0,0 +1,466 @@
+//! Decoder and encoder for the IRIG-B time code (B00x and B12x), with the IEEE 1344
+//! extensions in the control functions.
+//!
+//! An IRIG-B frame consists of 100 bits of 10 ms each, with a position identifier at
+//! every tenth bit and the reference marker at bit 0. Unlike the radio time codes, a
+//! frame describes the second it starts at, so it is decoded into the current minute.
+//! All BCD values are transmitted least significant bit first:
+//!
+//! * 1 through 8: second, 10 through 17: minute, 20 through 26: hour
+//! * 30 through 41: day of the year, 50 through 58: year
+//! * 60 through 78: control functions, see `Extensions`
+//! * 80 through 97: straight binary seconds of the day
+//!
+//! Bits which are not mentioned, except for the position identifiers, are always 0.
+
+use crate::radio_datetime_helpers::{
+    get_bcd_value_u16, get_day_of_year, get_month_day, get_weekday, is_leap_year,
+    is_leap_year_21st_century, set_bcd_value_u16,
+};
+use crate::utc::get_minute_length;
+use crate::{
+    FieldStatus, RadioDateTimeUtils, DST_ANNOUNCED, DST_SUMMER, LEAP_ANNOUNCED, LEAP_NEGATIVE,
+};
+
+/// Number of bits in a frame.
+pub const FRAME_SIZE: usize = 100;
+
+/// Bits of one frame, indexed by bit number.
+pub type Frame = [Option<bool>; FRAME_SIZE];
+
+/// Positions of the reference marker and the position identifiers.
+pub const MARKERS: [usize; 11] = [0, 9, 19, 29, 39, 49, 59, 69, 79, 89, 99];
+
+/// Numeric value of Sunday, IRIG-B does not transmit the day of the week.
+const SUNDAY: u8 = 0;
+
+/// Largest time offset in minutes which fits the control functions.
+const MAX_TIME_OFFSET: i16 = 15 * 60 + 30;
+
+/// The IEEE 1344 extensions, transmitted in the control functions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Extensions {
+    /// bit 60, a leap second takes place at the end of this minute
+    pub leap_second_pending: bool,
+    /// bit 61, the pending leap second is negative
+    pub leap_second_negative: bool,
+    /// bit 62, a DST change takes place at the end of this minute
+    pub dst_pending: bool,
+    /// bit 63, daylight saving time is in effect
+    pub dst: bool,
+    /// bits 64 through 68 and 70, the offset in minutes which is added to the transmitted
+    /// time to obtain UTC, a multiple of 30. Bit 64 is set for negative offsets.
+    pub time_offset: i16,
+    /// bits 71 through 74, the time quality with 0 being locked to UTC and 15 being
+    /// unreliable
+    pub time_quality: u8,
+}
+
+/// Per-field outcome of decoding one IRIG-B frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    /// the second of the frame, 0 through 60, None if absent or invalid
+    pub second: Option<u8>,
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    /// straight binary seconds of the day, None if absent or not matching the time
+    pub seconds_of_day: Option<u32>,
+    /// IEEE 1344 extensions, None if absent or if their parity is wrong
+    pub extensions: Option<Extensions>,
+}
+
+/// Returns the binary value of the given bits, least significant bit first, or None if
+/// any of them is absent.
+///
+/// # Arguments
+/// * `bits` - the bits
+fn get_binary_value(bits: &[Option<bool>]) -> Option<u32> {
+    bits.iter()
+        .rev()
+        .try_fold(0, |acc, b| Some(acc << 1 | (*b)? as u32))
+}
+
+/// Write the given value as binary into the given bits, least significant bit first.
+///
+/// # Arguments
+/// * `bits` - the bits to write to
+/// * `value` - the value to write, must fit the bits
+fn set_binary_value(bits: &mut [Option<bool>], value: u32) {
+    for (idx, bit) in bits.iter_mut().enumerate() {
+        *bit = Some((value >> idx) & 1 == 1);
+    }
+}
+
+/// Returns the even parity bit over the data bits 1 through 74, or None if any of them
+/// is absent. The position identifiers are skipped.
+///
+/// # Arguments
+/// * `frame` - the bits of the frame
+fn get_control_parity(frame: &Frame) -> Option<bool> {
+    let mut parity = false;
+    for (idx, bit) in frame.iter().enumerate().take(75).skip(1) {
+        if !MARKERS.contains(&idx) {
+            parity ^= (*bit)?;
+        }
+    }
+    Some(parity)
+}
+
+/// Returns the IEEE 1344 extensions in the given frame, or None if any of their bits is
+/// absent or if the even parity in bit 75 is wrong.
+///
+/// # Arguments
+/// * `frame` - the bits of the frame
+pub fn get_extensions(frame: &Frame) -> Option<Extensions> {
+    if get_control_parity(frame)? != frame[75]? {
+        return None;
+    }
+    let magnitude = 60 * get_binary_value(&frame[65..=68])? as i16 + 30 * frame[70]? as i16;
+    Some(Extensions {
+        leap_second_pending: frame[60]?,
+        leap_second_negative: frame[61]?,
+        dst_pending: frame[62]?,
+        dst: frame[63]?,
+        time_offset: if frame[64]? { -magnitude } else { magnitude },
+        time_quality: get_binary_value(&frame[71..=74])? as u8,
+    })
+}
+
+/// Decode one IRIG-B frame into the given date/time instance.
+///
+/// At second 0, the date/time is first advanced by one minute using `add_minute()`, so
+/// the decoded values can be checked for jumps against the expected ones. At any other
+/// second, they are checked against the current values. The day of the week is
+/// calculated from the date, with Sunday being 0.
+///
+/// The DST and leap second values are taken from the IEEE 1344 extensions, if present,
+/// once per minute at second 0 or at the first frame if they are not known yet. A
+/// pending change or leap second is announced during the last minute before it.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(0)`
+/// * `frame` - the bits of the frame. The values at the position markers are ignored.
+/// * `check_jump` - check if the values have jumped unexpectedly.
+pub fn decode_time(rdt: &mut RadioDateTimeUtils, frame: &Frame, check_jump: bool) -> DecodeReport {
+    // Ignore the position markers, some of them separate the digits of a field:
+    let mut bits = *frame;
+    for idx in MARKERS {
+        bits[idx] = Some(false);
+    }
+    let frame = &bits;
+    let second = get_bcd_value_u16(frame, &[(1, 4), (6, 8)])
+        .map(|s| s as u8)
+        .filter(|s| *s <= 60);
+    let advanced = if second == Some(0) {
+        rdt.add_minute()
+    } else {
+        rdt.is_valid()
+    };
+    let check_jump = check_jump && advanced;
+
+    let minute = get_bcd_value_u16(frame, &[(10, 13), (15, 17)]).map(|m| m as u8);
+    let hour = get_bcd_value_u16(frame, &[(20, 23), (25, 26)]).map(|h| h as u8);
+    let day_of_year = get_bcd_value_u16(frame, &[(30, 33), (35, 38), (40, 41)]);
+    let year = get_bcd_value_u16(frame, &[(50, 53), (55, 58)]).map(|y| y as u8);
+    let month_day = day_of_year
+        .zip(year.and_then(is_leap_year_21st_century))
+        .and_then(|(d, l)| get_month_day(d, l));
+    let month = month_day.map(|md| md.0);
+    let day = month_day.map(|md| md.1);
+    let weekday = year
+        .zip(month_day)
+        .and_then(|(y, (m, d))| get_weekday(y, m, d, SUNDAY));
+
+    rdt.set_minute(minute, true, check_jump);
+    rdt.set_hour(hour, true, check_jump);
+    rdt.set_year(year, true, check_jump);
+    rdt.set_month(month, true, check_jump);
+    rdt.set_weekday(weekday, true, check_jump);
+    // day must be set *after* year, month, and weekday
+    rdt.set_day(day, true, check_jump);
+
+    let extensions = get_extensions(frame);
+    if let Some(ext) = extensions.filter(|_| second == Some(0) || rdt.get_dst().is_none()) {
+        set_extensions(rdt, &ext, check_jump);
+    }
+
+    let seconds_of_day = get_binary_value(&frame[80..=88])
+        .zip(get_binary_value(&frame[90..=97]))
+        .map(|(low, high)| high << 9 | low)
+        .filter(|s| {
+            hour.zip(minute)
+                .zip(second)
+                .is_some_and(|((h, m), sec)| *s == h as u32 * 3600 + m as u32 * 60 + sec as u32)
+        });
+    DecodeReport {
+        second,
+        minute: FieldStatus::new(&frame[10..=17], Some(true), minute, rdt.get_minute()),
+        hour: FieldStatus::new(&frame[20..=26], Some(true), hour, rdt.get_hour()),
+        day: FieldStatus::new(&frame[30..=41], Some(true), day, rdt.get_day()),
+        weekday: FieldStatus::new(&frame[30..=41], Some(true), weekday, rdt.get_weekday()),
+        month: FieldStatus::new(&frame[30..=41], Some(true), month, rdt.get_month()),
+        year: FieldStatus::new(&frame[50..=58], Some(true), year, rdt.get_year()),
+        seconds_of_day,
+        extensions,
+    }
+}
+
+/// Set the DST and leap second values of the given date/time from the IEEE 1344
+/// extensions.
+///
+/// An announcement which was active in the previous minute is processed at minute 0. As
+/// a leap second can only be seen after the fact, an announced one is assumed to have
+/// taken place.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update
+/// * `ext` - the extensions of the current frame
+/// * `check_jump` - check if the DST value has jumped unexpectedly.
+fn set_extensions(rdt: &mut RadioDateTimeUtils, ext: &Extensions, check_jump: bool) {
+    rdt.set_dst(Some(ext.dst), Some(ext.dst_pending), check_jump);
+    let minute_length = rdt.get_minute().map_or(60, |minute| {
+        get_minute_length(rdt.get_leap_second().unwrap_or(0), minute)
+    });
+    rdt.set_signed_leap_second(
+        Some(ext.leap_second_pending),
+        Some(ext.leap_second_negative),
+        minute_length,
+    );
+}
+
+/// Encode the given date/time into one IRIG-B frame, the inverse of `decode_time()`.
+///
+/// The DST and leap second announcements are transmitted during minute 59 only. The
+/// position markers are None.
+///
+/// Returns the frame, or None if the date/time is not valid, or if `second`,
+/// `time_offset`, or `time_quality` is out of range.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the current minute
+/// * `second` - the second of the frame, 0 through 60
+/// * `time_offset` - the offset in minutes which is added to the transmitted time to
+///   obtain UTC, a multiple of 30 from -930 through 930
+/// * `time_quality` - the time quality, 0 through 15
+pub fn encode_time(
+    rdt: &RadioDateTimeUtils,
+    second: u8,
+    time_offset: i16,
+    time_quality: u8,
+) -> Option<Frame> {
+    if !rdt.is_valid()
+        || second > 60
+        || time_offset.abs() > MAX_TIME_OFFSET
+        || time_offset % 30 != 0
+        || time_quality > 15
+    {
+        return None;
+    }
+    let minute = rdt.get_minute().unwrap();
+    let hour = rdt.get_hour().unwrap();
+    let year = rdt.get_year().unwrap();
+    let day_of_year = get_day_of_year(
+        rdt.get_month().unwrap(),
+        rdt.get_day().unwrap(),
+        is_leap_year(rdt.get_full_year()?),
+    )?;
+    let dst = rdt.get_dst().unwrap();
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    let last_minute = minute == 59;
+
+    let mut frame = [Some(false); FRAME_SIZE];
+    set_bcd_value_u16(&mut frame, &[(1, 4), (6, 8)], second as u16);
+    set_bcd_value_u16(&mut frame, &[(10, 13), (15, 17)], minute as u16);
+    set_bcd_value_u16(&mut frame, &[(20, 23), (25, 26)], hour as u16);
+    set_bcd_value_u16(&mut frame, &[(30, 33), (35, 38), (40, 41)], day_of_year);
+    set_bcd_value_u16(&mut frame, &[(50, 53), (55, 58)], year as u16);
+    frame[60] = Some(last_minute && leap_second & LEAP_ANNOUNCED != 0);
+    frame[61] = Some(frame[60] == Some(true) && leap_second & LEAP_NEGATIVE != 0);
+    frame[62] = Some(last_minute && dst & DST_ANNOUNCED != 0);
+    frame[63] = Some(dst & DST_SUMMER != 0);
+    frame[64] = Some(time_offset < 0);
+    set_binary_value(&mut frame[65..=68], time_offset.unsigned_abs() as u32 / 60);
+    frame[70] = Some(!time_offset.unsigned_abs().is_multiple_of(60));
+    set_binary_value(&mut frame[71..=74], time_quality as u32);
+    frame[75] = get_control_parity(&frame);
+    let seconds_of_day = hour as u32 * 3600 + minute as u32 * 60 + second as u32;
+    set_binary_value(&mut frame[80..=88], seconds_of_day);
+    set_binary_value(&mut frame[90..=97], seconds_of_day >> 9);
+    for idx in MARKERS {
+        frame[idx] = None;
+    }
+    Some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rdt_at;
+    use crate::{DST_JUMP, DST_PROCESSED, LEAP_PROCESSED};
+
+    // 2024-03-10 (day 70) 01:23:45, EST (UTC-5), no extensions but the time offset
+    fn frame() -> Frame {
+        let mut frame = [Some(false); FRAME_SIZE];
+        set_bcd_value_u16(&mut frame, &[(1, 4), (6, 8)], 45);
+        set_bcd_value_u16(&mut frame, &[(10, 13), (15, 17)], 23);
+        set_bcd_value_u16(&mut frame, &[(20, 23), (25, 26)], 1);
+        set_bcd_value_u16(&mut frame, &[(30, 33), (35, 38), (40, 41)], 70);
+        set_bcd_value_u16(&mut frame, &[(50, 53), (55, 58)], 24);
+        // EST + 5 hours is UTC:
+        frame[65] = Some(true);
+        frame[67] = Some(true);
+        // 2 + 1 + 2 + 1 + 1 + 3 + 1 + 1 + 2 ones:
+        frame[75] = Some(false);
+        // 5025 seconds = 0b1_0011_1010_0001
+        for idx in [80, 85, 87, 88, 90, 93] {
+            frame[idx] = Some(true);
+        }
+        for idx in MARKERS {
+            frame[idx] = None;
+        }
+        frame
+    }
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &frame(), false);
+        assert_eq!(report.second, Some(45));
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(report.day, FieldStatus::Ok);
+        assert_eq!(report.seconds_of_day, Some(5025));
+        assert_eq!(
+            report.extensions,
+            Some(Extensions {
+                leap_second_pending: false,
+                leap_second_negative: false,
+                dst_pending: false,
+                dst: false,
+                time_offset: 300,
+                time_quality: 0,
+            })
+        );
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_month(), Some(3));
+        assert_eq!(rdt.get_day(), Some(10));
+        assert_eq!(rdt.get_weekday(), Some(0));
+        assert_eq!(rdt.get_hour(), Some(1));
+        assert_eq!(rdt.get_minute(), Some(23));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(encode_time(&rdt, 45, 300, 0), Some(frame()));
+    }
+    #[test]
+    fn test_decode_time_bad_parity() {
+        let mut bad = frame();
+        bad[71] = Some(true);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bad, false);
+        assert_eq!(report.extensions, None);
+        assert_eq!(rdt.get_dst(), None);
+        assert_eq!(rdt.get_minute(), Some(23));
+    }
+    #[test]
+    fn test_decode_time_bad_seconds_of_day() {
+        let mut bad = frame();
+        bad[81] = Some(true);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bad, false);
+        assert_eq!(report.seconds_of_day, None);
+        assert_eq!(report.second, Some(45));
+    }
+    #[test]
+    fn test_decode_time_missing_bit() {
+        let mut bad = frame();
+        bad[12] = None;
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bad, false);
+        assert_eq!(report.minute, FieldStatus::Missing);
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn continue_decode_time_dst_change() {
+        // DST starts at 02:00 EST on 2024-03-10, which becomes 03:00 EDT
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut expected = rdt_at(0, (24, 3, 10), (1, 58), false);
+        expected.set_dst(Some(false), Some(true), false);
+        assert_eq!(expected.get_dst(), Some(DST_ANNOUNCED));
+        for second in 0..60 {
+            let frame = encode_time(&expected, second, 300, 0).unwrap();
+            assert_eq!(frame[62], Some(false));
+            decode_time(&mut rdt, &frame, true);
+        }
+        expected.set_minute(Some(59), true, false);
+        for second in 0..60 {
+            let frame = encode_time(&expected, second, 300, 0).unwrap();
+            assert_eq!(frame[62], Some(true));
+            decode_time(&mut rdt, &frame, true);
+        }
+        assert_eq!(rdt.get_dst(), Some(DST_ANNOUNCED));
+        expected.set_hour(Some(3), true, false);
+        expected.set_minute(Some(0), true, false);
+        expected.set_dst(Some(true), Some(false), false);
+        let frame = encode_time(&expected, 0, 240, 0).unwrap();
+        decode_time(&mut rdt, &frame, true);
+        assert_eq!(rdt.get_hour(), Some(3));
+        assert!(!rdt.get_jump_hour());
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_PROCESSED));
+        let frame = encode_time(&expected, 1, 240, 0).unwrap();
+        decode_time(&mut rdt, &frame, true);
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_PROCESSED));
+        expected.set_minute(Some(1), true, false);
+        let frame = encode_time(&expected, 0, 240, 0).unwrap();
+        decode_time(&mut rdt, &frame, true);
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn continue_decode_time_dst_jump() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(&mut rdt, &frame(), true);
+        // DST starts unannounced at the next minute:
+        let mut bad = encode_time(&rdt_at(0, (24, 3, 10), (1, 24), false), 0, 300, 0).unwrap();
+        bad[63] = Some(true);
+        bad[75] = bad[75].map(|p| !p);
+        decode_time(&mut rdt, &bad, true);
+        assert_eq!(rdt.get_minute(), Some(24));
+        assert_eq!(rdt.get_dst(), Some(DST_JUMP));
+    }
+    #[test]
+    fn continue_decode_time_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut expected = rdt_at(0, (24, 3, 31), (23, 59), false);
+        expected.set_leap_second(Some(true), 60);
+        assert_eq!(expected.get_leap_second(), Some(LEAP_ANNOUNCED));
+        for second in 0..=60 {
+            let frame = encode_time(&expected, second, 0, 0).unwrap();
+            let report = decode_time(&mut rdt, &frame, true);
+            assert_eq!(report.second, Some(second));
+        }
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        let mut expected = rdt_at(0, (24, 3, 1), (0, 0), false);
+        expected.set_month(Some(4), true, false);
+        expected.set_weekday(Some(1), true, false);
+        expected.set_day(Some(1), true, false);
+        let frame = encode_time(&expected, 0, 0, 0).unwrap();
+        decode_time(&mut rdt, &frame, true);
+        assert_eq!(rdt.get_month(), Some(4));
+        assert!(!rdt.get_jump_month());
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+    }
+    #[test]
+    fn bad_encode_time() {
+        let rdt = rdt_at(0, (24, 3, 10), (1, 23), false);
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(0), 0, 0, 0), None);
+        assert_eq!(encode_time(&rdt, 61, 0, 0), None);
+        assert_eq!(encode_time(&rdt, 0, 20, 0), None);
+        assert_eq!(encode_time(&rdt, 0, -960, 0), None);
+        assert_eq!(encode_time(&rdt, 0, 0, 16), None);
+        let frame = encode_time(&rdt, 0, 330, 15).unwrap();
+        let extensions = get_extensions(&frame).unwrap();
+        assert_eq!(extensions.time_offset, 330);
+        assert_eq!(extensions.time_quality, 15);
+    }
+}
@@ -0,0 +1,518 @@
+//! Decoder for the time code of JJY (Ohtakadoya-yama 40 kHz and Hagane-yama 60 kHz, Japan).
+//!
+//! JJY transmits Japan Standard Time (UTC+9) without daylight saving time, the time code
+//! of a minute describes the minute it is transmitted in.
+
+use crate::radio_datetime_helpers::{
+    add_minute_day_of_year, get_bcd_value, get_bcd_value_u16, get_day_of_year, get_month_day,
+    get_parity, is_leap_year, is_leap_year_21st_century, set_bcd_value, set_bcd_value_u16,
+    sub_minute_day_of_year,
+};
+use crate::utc::get_minute_length;
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, LEAP_ANNOUNCED, LEAP_NEGATIVE, LEAP_PROCESSED,
+};
+
+/// Positions of the position markers, including the reference marker at second 0.
+pub const MARKERS: [usize; 7] = [0, 9, 19, 29, 39, 49, 59];
+
+/// Positions of the unused bits, which are always 0.
+const UNUSED: [usize; 9] = [4, 10, 11, 14, 20, 21, 24, 34, 35];
+
+/// Per-field outcome of decoding one JJY minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub leap_second: FieldStatus,
+    /// The minute transmitted the call sign instead of the year, day-of-week and leap second bits
+    pub call_sign: bool,
+    /// Leap second warning: 1 for a positive leap second, -1 for a negative one, 0 for none
+    pub leap_second_warning: Option<i8>,
+}
+
+/// Decode the bit buffer of one JJY minute into the given date/time instance.
+///
+/// Like with DCF77 and MSF, the date/time is set to the minute starting at the end of
+/// the bit buffer, so one minute is added to the transmitted time.
+///
+/// In the call sign minutes at 15 and 45 minutes past the hour, the year (needed to
+/// convert the day-of-year) is taken from `rdt`, and the day of the week is not updated.
+///
+/// The date/time is first advanced by one minute using `add_minute()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(0)`
+/// * `bit_buffer` - the bits of the minute, indexed by second. The values at the
+///   position markers are ignored.
+/// * `minute_length` - the length of the decoded minute in seconds, 59 through 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    let added_minute = rdt.add_minute();
+    let check_jump = check_jump && added_minute;
+    // Ignore the position markers, some of them separate the digits of a field:
+    let mut bits = *bit_buffer;
+    for idx in MARKERS {
+        bits[idx] = Some(false);
+    }
+    let bit_buffer = &bits;
+    let frame_ok = (59..=61).contains(&minute_length)
+        && UNUSED.iter().all(|idx| bit_buffer[*idx] != Some(true));
+
+    // Both parities are even:
+    let parity_hour = get_parity(bit_buffer, 12, 18, bit_buffer[36]).map(|p| !p);
+    let parity_minute = get_parity(bit_buffer, 1, 8, bit_buffer[37]).map(|p| !p);
+    let mut minute = get_bcd_value_u16(bit_buffer, &[(8, 5), (3, 1)])
+        .filter(|_| frame_ok && parity_minute == Some(true))
+        .map(|m| m as u8);
+    let call_sign = minute == Some(15) || minute == Some(45);
+    let mut hour = get_bcd_value_u16(bit_buffer, &[(18, 15), (13, 12)])
+        .filter(|_| frame_ok && parity_hour == Some(true))
+        .map(|h| h as u8);
+    let mut day_of_year =
+        get_bcd_value_u16(bit_buffer, &[(33, 30), (28, 25), (23, 22)]).filter(|_| frame_ok);
+    let mut year = if call_sign {
+        rdt.get_year()
+    } else {
+        get_bcd_value(bit_buffer, 48, 41).filter(|_| frame_ok)
+    };
+    let mut leap_year = year.and_then(is_leap_year_21st_century);
+    let mut weekday = if call_sign {
+        None
+    } else {
+        get_bcd_value(bit_buffer, 52, 50).filter(|_| frame_ok)
+    };
+    add_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+    if hour.is_none() {
+        weekday = None;
+    } else if hour == Some(0) && minute == Some(0) {
+        weekday = weekday.map(|wd| (wd + 1) % 7);
+    }
+    let month_day = day_of_year
+        .zip(leap_year)
+        .and_then(|(d, l)| get_month_day(d, l));
+    let month = month_day.map(|md| md.0);
+    let day = month_day.map(|md| md.1);
+    let year = year.filter(|_| !call_sign);
+
+    rdt.set_minute(minute, true, check_jump);
+    rdt.set_hour(hour, true, check_jump);
+    rdt.set_year(year, true, check_jump);
+    rdt.set_month(month, true, check_jump);
+    rdt.set_weekday(weekday, true, check_jump);
+    // day must be set *after* year, month, and weekday
+    rdt.set_day(day, true, check_jump);
+
+    // Japan does not observe daylight saving time:
+    rdt.set_dst(Some(false), Some(false), check_jump);
+    let leap_second_warning = if call_sign {
+        Some(0)
+    } else {
+        match (bit_buffer[53], bit_buffer[54]) {
+            (Some(false), Some(_)) => Some(0),
+            (Some(true), Some(true)) => Some(1),
+            (Some(true), Some(false)) => Some(-1),
+            _ => None,
+        }
+    };
+    // The leap second is at the end of 08:59 JST on the first day of the month,
+    // only count the warning during the hour before it:
+    let last_hour = hour == Some(8) && day == Some(1);
+    rdt.set_signed_leap_second(
+        leap_second_warning.map(|w| w != 0 && last_hour),
+        leap_second_warning.map(|w| w == -1),
+        minute_length,
+    );
+    rdt.bump_minutes_running();
+
+    DecodeReport {
+        minute: FieldStatus::new(&bit_buffer[1..=8], parity_minute, minute, rdt.get_minute()),
+        hour: FieldStatus::new(&bit_buffer[12..=18], parity_hour, hour, rdt.get_hour()),
+        day: FieldStatus::new(&bit_buffer[22..=33], Some(true), day, rdt.get_day()),
+        weekday: if call_sign {
+            FieldStatus::Ok
+        } else {
+            FieldStatus::new(&bit_buffer[50..=52], Some(true), weekday, rdt.get_weekday())
+        },
+        month: FieldStatus::new(&bit_buffer[22..=33], Some(true), month, rdt.get_month()),
+        year: if call_sign {
+            FieldStatus::Ok
+        } else {
+            FieldStatus::new(&bit_buffer[41..=48], Some(true), year, rdt.get_year())
+        },
+        leap_second: FieldStatus::new(
+            &bit_buffer[53..=54],
+            Some(true),
+            Some(minute_length).filter(|m| (59..=61).contains(m)),
+            Some(minute_length),
+        ),
+        call_sign,
+        leap_second_warning,
+    }
+}
+
+/// Encode the given date/time into the bit buffer of one JJY minute, the inverse of
+/// `decode_time()`.
+///
+/// The transmitted minute is the one before `rdt`. Position markers, the call sign in
+/// Morse code at seconds 40 through 48 of minutes 15 and 45, and unused bits at the end
+/// are None.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<bool>; BIT_BUFFER_SIZE], u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let mut minute = rdt.get_minute().unwrap();
+    let mut hour = rdt.get_hour().unwrap();
+    let mut year = rdt.get_year().unwrap();
+    let mut leap_year = is_leap_year(rdt.get_full_year()?);
+    let mut day_of_year =
+        get_day_of_year(rdt.get_month().unwrap(), rdt.get_day().unwrap(), leap_year)?;
+    // Sunday is 0:
+    let mut weekday = rdt.get_weekday().unwrap() % 7;
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    let leap_warning =
+        leap_second & LEAP_ANNOUNCED != 0 || minute == 0 && leap_second & LEAP_PROCESSED != 0;
+    let negative = leap_second & LEAP_NEGATIVE != 0;
+    let minute_length = get_minute_length(leap_second, minute);
+    sub_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+    if hour == 23 && minute == 59 {
+        weekday = (weekday + 6) % 7;
+    }
+
+    let mut bit_buffer = [Some(false); BIT_BUFFER_SIZE];
+    for bit in &mut bit_buffer[minute_length as usize..] {
+        *bit = None;
+    }
+    set_bcd_value_u16(&mut bit_buffer, &[(8, 5), (3, 1)], minute as u16);
+    set_bcd_value_u16(&mut bit_buffer, &[(18, 15), (13, 12)], hour as u16);
+    set_bcd_value_u16(
+        &mut bit_buffer,
+        &[(33, 30), (28, 25), (23, 22)],
+        day_of_year,
+    );
+    // Both parities are even:
+    bit_buffer[36] = get_parity(&bit_buffer, 12, 18, Some(false));
+    bit_buffer[37] = get_parity(&bit_buffer, 1, 8, Some(false));
+    if minute == 15 || minute == 45 {
+        for bit in &mut bit_buffer[40..=48] {
+            *bit = None;
+        }
+    } else {
+        set_bcd_value(&mut bit_buffer, 48, 41, year);
+        set_bcd_value(&mut bit_buffer, 52, 50, weekday);
+        bit_buffer[53] = Some(leap_warning);
+        bit_buffer[54] = Some(leap_warning && !negative);
+    }
+    for idx in MARKERS {
+        bit_buffer[idx] = None;
+    }
+    Some((bit_buffer, minute_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio_datetime_helpers::set_parity;
+    use crate::{LEAP_ANNOUNCED, LEAP_PROCESSED};
+
+    /// Build a minute with the given date/time and leap second bits LS1/LS2.
+    fn frame(
+        year: u8,
+        doy: u16,
+        weekday: u8,
+        hour: u8,
+        minute: u8,
+        leap: (bool, bool),
+    ) -> [Option<bool>; BIT_BUFFER_SIZE] {
+        let mut buffer = [Some(false); BIT_BUFFER_SIZE];
+        for idx in MARKERS {
+            buffer[idx] = None;
+        }
+        buffer[60] = None;
+        buffer[61] = None;
+        set_bcd_value_u16(&mut buffer, &[(8, 5), (3, 1)], minute as u16);
+        set_bcd_value_u16(&mut buffer, &[(18, 15), (13, 12)], hour as u16);
+        set_bcd_value_u16(&mut buffer, &[(33, 30), (28, 25), (23, 22)], doy);
+        set_parity(&mut buffer, 12, 18, 36, false);
+        set_parity(&mut buffer, 1, 8, 37, false);
+        if minute == 15 || minute == 45 {
+            // "JJY" in Morse code, then the service interruption bits
+            for (idx, bit) in [1, 0, 1, 1, 1, 0, 1, 1, 1].into_iter().enumerate() {
+                buffer[40 + idx] = Some(bit == 1);
+            }
+        } else {
+            set_bcd_value(&mut buffer, 48, 41, year);
+            set_bcd_value(&mut buffer, 52, 50, weekday);
+            buffer[53] = Some(leap.0);
+            buffer[54] = Some(leap.1);
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Thursday 2024-02-29 12:34 JST, so 12:35 at the end of the minute
+        let report = decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 34, (false, false)),
+            60,
+            true,
+        );
+        assert_eq!(
+            report,
+            DecodeReport {
+                minute: FieldStatus::Ok,
+                hour: FieldStatus::Ok,
+                day: FieldStatus::Ok,
+                weekday: FieldStatus::Ok,
+                month: FieldStatus::Ok,
+                year: FieldStatus::Ok,
+                leap_second: FieldStatus::Ok,
+                call_sign: false,
+                leap_second_warning: Some(0),
+            }
+        );
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_day(), Some(29));
+        assert_eq!(rdt.get_month(), Some(2));
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert!(rdt.is_valid());
+    }
+    #[test]
+    fn test_decode_time_saturday_sunday() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Saturday 2024-03-02 23:59 JST
+        decode_time(
+            &mut rdt,
+            &frame(24, 62, 6, 23, 59, (false, false)),
+            60,
+            true,
+        );
+        assert_eq!(rdt.get_minute(), Some(0));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_day(), Some(3));
+        assert_eq!(rdt.get_weekday(), Some(0));
+    }
+    #[test]
+    fn continue_decode_time_call_sign() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 14, (false, false)),
+            60,
+            true,
+        );
+        let report = decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 15, (false, false)),
+            60,
+            true,
+        );
+        assert!(report.call_sign);
+        assert_eq!(report.year, FieldStatus::Ok);
+        assert_eq!(report.day, FieldStatus::Ok);
+        assert_eq!(rdt.get_minute(), Some(16));
+        assert_eq!(rdt.get_day(), Some(29));
+        assert_eq!(rdt.get_year(), Some(24));
+        assert!(!rdt.get_jump_minute());
+        assert!(!rdt.get_jump_year());
+    }
+    #[test]
+    fn test_decode_time_call_sign_first_minute() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 45, (false, false)),
+            60,
+            true,
+        );
+        assert!(report.call_sign);
+        assert_eq!(report.day, FieldStatus::Invalid); // no year known yet
+        assert_eq!(rdt.get_minute(), Some(46));
+        assert_eq!(rdt.get_year(), None);
+    }
+    #[test]
+    fn test_decode_time_bad_parity_hour() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(24, 60, 4, 12, 34, (false, false));
+        buffer[36] = Some(!buffer[36].unwrap());
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.hour, FieldStatus::BadParity);
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(rdt.get_hour(), None);
+    }
+    #[test]
+    fn test_decode_time_negative_leap_second_warning() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 34, (true, false)),
+            60,
+            false,
+        );
+        assert_eq!(report.leap_second_warning, Some(-1));
+    }
+    #[test]
+    fn continue_decode_time_positive_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Sunday 2017-01-01 is day 1, the leap second is at the end of 08:59 JST
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(17, 1, 0, 8, minute, (true, true)),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        decode_time(&mut rdt, &frame(17, 1, 0, 8, 59, (true, true)), 61, true);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+        assert_eq!(rdt.get_hour(), Some(9));
+    }
+    #[test]
+    fn continue_decode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(17, 1, 0, 8, minute, (true, false)),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED | LEAP_NEGATIVE));
+        let report = decode_time(&mut rdt, &frame(17, 1, 0, 8, 59, (true, false)), 59, true);
+        assert_eq!(report.leap_second, FieldStatus::Ok);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+        assert_eq!(rdt.get_hour(), Some(9));
+    }
+
+    #[test]
+    fn test_encode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let buffer = frame(24, 60, 4, 12, 34, (false, false));
+        decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(encode_time(&rdt), Some((buffer, 60)));
+    }
+    #[test]
+    fn test_encode_time_round_trip() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Saturday 2024-03-02 12:00 JST
+        decode_time(
+            &mut rdt,
+            &frame(24, 62, 6, 11, 59, (false, false)),
+            60,
+            false,
+        );
+        for _ in 0..(24 * 60) {
+            let mut next_rdt = rdt;
+            assert!(next_rdt.add_minute());
+            let (buffer, minute_length) = encode_time(&next_rdt).unwrap();
+            let report = decode_time(&mut rdt, &buffer, minute_length, true);
+            assert_eq!(report.weekday, FieldStatus::Ok);
+            assert_eq!(report.year, FieldStatus::Ok);
+            assert!(!rdt.get_jump_minute() && !rdt.get_jump_day() && !rdt.get_jump_weekday());
+        }
+        assert_eq!(rdt.get_day(), Some(3));
+        assert_eq!(rdt.get_weekday(), Some(0));
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_minute(), Some(0));
+    }
+    #[test]
+    fn test_encode_time_call_sign() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 14, (false, false)),
+            60,
+            false,
+        );
+        decode_time(
+            &mut rdt,
+            &frame(24, 60, 4, 12, 15, (false, false)),
+            60,
+            false,
+        );
+        let (buffer, _) = encode_time(&rdt).unwrap();
+        assert!(buffer[40..=48].iter().all(|bit| bit.is_none()));
+        assert_eq!(buffer[53], Some(false));
+    }
+    #[test]
+    fn test_encode_time_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Sunday 2017-01-01 is day 1
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(17, 1, 0, 8, minute, (true, true)),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        let mut next_rdt = rdt;
+        assert!(next_rdt.add_minute());
+        let (buffer, minute_length) = encode_time(&next_rdt).unwrap();
+        assert_eq!(minute_length, 61);
+        assert_eq!((buffer[53], buffer[54]), (Some(true), Some(true)));
+        assert_eq!(buffer[60], Some(false));
+        decode_time(&mut rdt, &buffer, minute_length, true);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+    }
+    #[test]
+    fn test_encode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(17, 1, 0, 8, minute, (true, false)),
+                60,
+                true,
+            );
+        }
+        let mut next_rdt = rdt;
+        assert!(next_rdt.add_minute());
+        let (buffer, minute_length) = encode_time(&next_rdt).unwrap();
+        assert_eq!(minute_length, 59);
+        assert_eq!((buffer[53], buffer[54]), (Some(true), Some(false)));
+        assert_eq!(buffer[59], None);
+        decode_time(&mut rdt, &buffer, minute_length, true);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(0)), None);
+    }
+}
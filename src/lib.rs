@@ -3,7 +3,27 @@
 //! Build with no_std for embedded platforms.
 #![cfg_attr(not(test), no_std)]
 
+pub mod bpc;
+#[cfg(feature = "chrono")]
+mod chrono_support;
+pub mod dcf77;
+pub mod dcf77_pm;
+pub mod irig;
+pub mod jjy;
+pub mod marker;
+pub mod msf;
+pub mod pll;
+pub mod pulse;
 pub mod radio_datetime_helpers;
+pub mod rbu;
+pub mod tdf;
+#[cfg(feature = "time")]
+mod time_support;
+pub mod utc;
+pub mod voting;
+pub mod wwv;
+pub mod wwvb;
+pub mod wwvb_pm;
 
 /// DST change has been announced
 pub const DST_ANNOUNCED: u8 = 1;
@@ -20,11 +40,292 @@ pub const LEAP_ANNOUNCED: u8 = 1;
 pub const LEAP_PROCESSED: u8 = 2;
 /// Leap second is unexpectedly absent
 pub const LEAP_MISSING: u8 = 4;
+/// Leap second is negative, i.e. the minute is 59 seconds long
+pub const LEAP_NEGATIVE: u8 = 8;
 
 /// Size of bit buffer in seconds plus one spare because we cannot know
 /// which method accessing the buffer is called after increase_second().
 pub const BIT_BUFFER_SIZE: usize = 61 + 1;
 
+/// Typed view of the daylight saving time bitmask returned by `get_dst()`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DstStatus(u8);
+
+impl DstStatus {
+    /// Initialize a new DstStatus instance from a DST bitmask.
+    ///
+    /// # Arguments
+    /// * `bits` - the bitmask made of the `DST_*` values
+    pub fn new(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Get the underlying bitmask.
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns if daylight saving time is active.
+    pub fn is_summer(&self) -> bool {
+        self.0 & DST_SUMMER != 0
+    }
+
+    /// Returns if a DST change has been announced.
+    pub fn change_announced(&self) -> bool {
+        self.0 & DST_ANNOUNCED != 0
+    }
+
+    /// Returns if a DST change has been processed at the start of this hour.
+    pub fn change_processed(&self) -> bool {
+        self.0 & DST_PROCESSED != 0
+    }
+
+    /// Returns if the DST state jumped unexpectedly.
+    pub fn jumped(&self) -> bool {
+        self.0 & DST_JUMP != 0
+    }
+}
+
+impl core::fmt::Debug for DstStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DstStatus")
+            .field("summer", &self.is_summer())
+            .field("announced", &self.change_announced())
+            .field("processed", &self.change_processed())
+            .field("jump", &self.jumped())
+            .finish()
+    }
+}
+
+impl core::fmt::Display for DstStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", if self.is_summer() { "summer" } else { "winter" })?;
+        if self.change_announced() {
+            write!(f, ", change announced")?;
+        }
+        if self.change_processed() {
+            write!(f, ", change processed")?;
+        }
+        if self.jumped() {
+            write!(f, ", jump")?;
+        }
+        Ok(())
+    }
+}
+
+/// Typed view of the leap second bitmask returned by `get_leap_second()`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct LeapSecondStatus(u8);
+
+impl LeapSecondStatus {
+    /// Initialize a new LeapSecondStatus instance from a leap second bitmask.
+    ///
+    /// # Arguments
+    /// * `bits` - the bitmask made of the `LEAP_*` values
+    pub fn new(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Get the underlying bitmask.
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns if a leap second has been announced.
+    pub fn leap_announced(&self) -> bool {
+        self.0 & LEAP_ANNOUNCED != 0
+    }
+
+    /// Returns if a leap second has been processed at the start of this hour.
+    pub fn leap_processed(&self) -> bool {
+        self.0 & LEAP_PROCESSED != 0
+    }
+
+    /// Returns if a processed leap second was unexpectedly absent.
+    pub fn leap_missing(&self) -> bool {
+        self.0 & LEAP_MISSING != 0
+    }
+
+    /// Returns if the announced or processed leap second is negative.
+    pub fn is_negative(&self) -> bool {
+        self.0 & LEAP_NEGATIVE != 0
+    }
+}
+
+impl core::fmt::Debug for LeapSecondStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LeapSecondStatus")
+            .field("announced", &self.leap_announced())
+            .field("processed", &self.leap_processed())
+            .field("missing", &self.leap_missing())
+            .field("negative", &self.is_negative())
+            .finish()
+    }
+}
+
+impl core::fmt::Display for LeapSecondStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_negative() {
+            write!(f, "negative ")?;
+        }
+        if self.leap_announced() {
+            write!(f, "leap second announced")
+        } else if self.leap_missing() {
+            write!(f, "leap second missing")
+        } else if self.leap_processed() {
+            write!(f, "leap second processed")
+        } else {
+            write!(f, "no leap second")
+        }
+    }
+}
+
+/// Outcome of decoding a single field of a time code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldStatus {
+    /// The field was decoded and stored.
+    Ok,
+    /// One or more bits of the field or its parity range are absent.
+    Missing,
+    /// The parity check over the field failed.
+    BadParity,
+    /// The field decoded to an invalid value, so the old value was kept.
+    Invalid,
+}
+
+impl FieldStatus {
+    /// Classify the outcome of decoding and storing a field.
+    ///
+    /// # Arguments
+    /// * `bits` - the bits making up the field
+    /// * `parity_ok` - if the parity check passed, None if it could not be calculated.
+    ///   Use Some(true) for fields without parity.
+    /// * `value` - the decoded value, None if it could not be decoded
+    /// * `stored` - the value present after the corresponding `set_*()` call
+    pub fn new(
+        bits: &[Option<bool>],
+        parity_ok: Option<bool>,
+        value: Option<u8>,
+        stored: Option<u8>,
+    ) -> Self {
+        if bits.iter().any(|b| b.is_none()) || parity_ok.is_none() {
+            Self::Missing
+        } else if parity_ok == Some(false) {
+            Self::BadParity
+        } else if value.is_none() || value != stored {
+            Self::Invalid
+        } else {
+            Self::Ok
+        }
+    }
+}
+
+/// Reason why decoding or storing a value failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bit at the given position is absent.
+    MissingBit { index: usize },
+    /// A BCD digit is greater than 9.
+    BadNibble,
+    /// The value is outside the valid range for its field.
+    OutOfRange,
+    /// The parity check failed.
+    ParityMismatch,
+    /// The requested range of bits is too wide for the result type.
+    RangeTooWide,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MissingBit { index } => write!(f, "missing bit at position {index}"),
+            Self::BadNibble => write!(f, "BCD digit greater than 9"),
+            Self::OutOfRange => write!(f, "value out of range"),
+            Self::ParityMismatch => write!(f, "parity mismatch"),
+            Self::RangeTooWide => write!(f, "range of bits too wide"),
+        }
+    }
+}
+
+impl From<DecodeError> for FieldStatus {
+    fn from(error: DecodeError) -> Self {
+        match error {
+            DecodeError::MissingBit { .. } => Self::Missing,
+            DecodeError::ParityMismatch => Self::BadParity,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+/// Time stations whose time zone rules are known to this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Station {
+    /// DCF77 (Germany), CET or CEST
+    Dcf77,
+    /// MSF (United Kingdom), GMT or BST
+    Msf,
+    /// WWVB (USA), UTC
+    Wwvb,
+    /// JJY (Japan), JST
+    Jjy,
+    /// BPC (China), CST
+    Bpc,
+    /// TDF (France), CET or CEST
+    Tdf,
+    /// RBU (Russia), MSK
+    Rbu,
+}
+
+impl Station {
+    /// Returns the offset of the transmitted time to UTC in minutes.
+    ///
+    /// # Arguments
+    /// * `summer` - if daylight saving time is active
+    pub fn get_utc_offset(&self, summer: bool) -> i16 {
+        match self {
+            Self::Dcf77 | Self::Tdf => 60 + 60 * summer as i16,
+            Self::Msf => 60 * summer as i16,
+            Self::Wwvb => 0,
+            Self::Jjy => 9 * 60,
+            Self::Bpc => 8 * 60,
+            Self::Rbu => 3 * 60,
+        }
+    }
+
+    /// Returns the numeric value of Sunday used by the decoder of this station, to be
+    /// passed to `RadioDateTimeUtils::new()`.
+    pub fn get_sunday(&self) -> u8 {
+        match self {
+            Self::Dcf77 | Self::Tdf | Self::Bpc | Self::Rbu => 7,
+            Self::Msf | Self::Wwvb | Self::Jjy => 0,
+        }
+    }
+}
+
+/// Error returned when a date/time cannot be converted into another representation,
+/// e.g. because it is incomplete or its century cannot be determined.
+#[cfg(any(feature = "chrono", feature = "time"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConversionError;
+
+/// A date/time in the local time of a station, used to seed the decoder state.
+#[cfg(any(feature = "chrono", feature = "time"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LocalDateTime {
+    /// the full year
+    pub year: u16,
+    /// month, 1 through 12
+    pub month: u8,
+    /// day of the month
+    pub day: u8,
+    /// hour, 0 through 23
+    pub hour: u8,
+    /// minute, 0 through 59
+    pub minute: u8,
+    /// if daylight saving time is active
+    pub summer: bool,
+}
+
 /// Represents a date and time transmitted over radio.
 #[derive(Clone, Copy)]
 pub struct RadioDateTimeUtils {
@@ -44,10 +345,12 @@ pub struct RadioDateTimeUtils {
     jump_minute: bool,
     min_weekday: u8,
     max_weekday: u8,
+    pivot_year: Option<u16>,
     minutes_running: u8,   // internal counter for set_dst() and set_leap_second()
     dst_count: u8,         // internal counter for set_dst()
     first_minute: bool,    // internal flag for set_dst()
     leap_second_count: u8, // internal counter for set_leap_second()
+    leap_second_negative_count: u8, // internal counter for set_signed_leap_second()
 }
 
 impl RadioDateTimeUtils {
@@ -59,7 +362,7 @@ impl RadioDateTimeUtils {
     /// # Arguments
     /// * `second` - the value of the current second (so normally 0..59)
     /// * `new_minute` - whether a new minute arrived
-    /// * `minute_length` - the length of this minute in seconds
+    /// * `minute_length` - the length of this minute in seconds, 59 through 61
     pub fn increase_second(second: &mut u8, new_minute: bool, minute_length: u8) -> bool {
         if new_minute {
             *second = 0;
@@ -92,6 +395,7 @@ impl RadioDateTimeUtils {
             dst_count: 0,
             leap_second: None,
             leap_second_count: 0,
+            leap_second_negative_count: 0,
             jump_year: false,
             jump_month: false,
             jump_day: false,
@@ -100,6 +404,7 @@ impl RadioDateTimeUtils {
             jump_minute: false,
             min_weekday: (sunday != 0) as u8,
             max_weekday: if sunday == 7 { 7 } else { 6 },
+            pivot_year: None,
             minutes_running: 0,
             first_minute: true,
         }
@@ -110,6 +415,50 @@ impl RadioDateTimeUtils {
         self.year
     }
 
+    /// Get the current year including its century, or None if it cannot be determined.
+    ///
+    /// With a pivot year set, the year is mapped into the hundred years starting at it.
+    /// Otherwise, the century is derived from the date and the day of the week, which
+    /// repeat every 400 years, so the result is in the range 1900 through 2299.
+    pub fn get_full_year(&self) -> Option<u16> {
+        self.get_full_year_of(self.year, self.month, self.day, self.weekday)
+    }
+
+    /// Get the year of the given date including its century, like `get_full_year()`.
+    ///
+    /// Decoders use this to determine leap years with the same rule as the encoders.
+    ///
+    /// # Arguments
+    /// * `year` - the year, truncated to two digits
+    /// * `month` - the month, only needed without a pivot year
+    /// * `day` - the day of the month, only needed without a pivot year
+    /// * `weekday` - the day of the week, only needed without a pivot year
+    pub(crate) fn get_full_year_of(
+        &self,
+        year: Option<u8>,
+        month: Option<u8>,
+        day: Option<u8>,
+        weekday: Option<u8>,
+    ) -> Option<u16> {
+        let s_year = year? as u16;
+        if let Some(pivot_year) = self.pivot_year {
+            return Some(pivot_year + (s_year + 100 - pivot_year % 100) % 100);
+        }
+        let s_month = month?;
+        let s_day = day?;
+        let s_weekday = weekday?;
+        (19..=22).map(|c| 100 * c + s_year).find(|year| {
+            radio_datetime_helpers::get_full_year_weekday(*year, s_month, s_day, self.sunday())
+                == Some(s_weekday)
+                && radio_datetime_helpers::get_day_of_year(
+                    s_month,
+                    s_day,
+                    radio_datetime_helpers::is_leap_year(*year),
+                )
+                .is_some()
+        })
+    }
+
     /// Get the current month.
     pub fn get_month(&self) -> Option<u8> {
         self.month
@@ -145,6 +494,16 @@ impl RadioDateTimeUtils {
         self.leap_second
     }
 
+    /// Get the current daylight saving time status (if any) with named accessors.
+    pub fn get_dst_status(&self) -> Option<DstStatus> {
+        self.dst.map(DstStatus::new)
+    }
+
+    /// Get the current leap second status (if any) with named accessors.
+    pub fn get_leap_second_status(&self) -> Option<LeapSecondStatus> {
+        self.leap_second.map(LeapSecondStatus::new)
+    }
+
     /// Return if the year has jumped unexpectedly.
     pub fn get_jump_year(&self) -> bool {
         self.jump_year
@@ -175,6 +534,57 @@ impl RadioDateTimeUtils {
         self.jump_minute
     }
 
+    /// Set the pivot year used to determine the century of the year.
+    ///
+    /// # Arguments
+    /// * `pivot_year` - the first full year of the hundred years the two-digit year is
+    ///   mapped into, i.e. 2000 for the 21st century or 1970 for 1970 through 2069.
+    ///   None derives the century from the date and the day of the week.
+    pub fn set_pivot_year(&mut self, pivot_year: Option<u16>) {
+        self.pivot_year = pivot_year;
+    }
+
+    /// Overwrite the date/time with a known-good value, e.g. from a system clock.
+    ///
+    /// Returns an error if the value is invalid, or if its year cannot be recovered by
+    /// `get_full_year()`, i.e. it is outside the hundred years starting at the pivot year
+    /// or, without a pivot year, outside 1900 through 2299.
+    ///
+    /// # Arguments
+    /// * `value` - the date/time to use
+    #[cfg(any(feature = "chrono", feature = "time"))]
+    pub(crate) fn seed(&mut self, value: &LocalDateTime) -> Result<(), ConversionError> {
+        let years = match self.pivot_year {
+            Some(pivot_year) => pivot_year..=pivot_year.saturating_add(99),
+            None => 1900..=2299,
+        };
+        if !years.contains(&value.year) || value.hour > 23 || value.minute > 59 {
+            return Err(ConversionError);
+        }
+        radio_datetime_helpers::get_day_of_year(
+            value.month,
+            value.day,
+            radio_datetime_helpers::is_leap_year(value.year),
+        )
+        .ok_or(ConversionError)?;
+        self.year = Some((value.year % 100) as u8);
+        self.month = Some(value.month);
+        self.day = Some(value.day);
+        self.weekday = radio_datetime_helpers::get_full_year_weekday(
+            value.year,
+            value.month,
+            value.day,
+            self.sunday(),
+        );
+        self.hour = Some(value.hour);
+        self.minute = Some(value.minute);
+        self.dst = Some(if value.summer { DST_SUMMER } else { 0 });
+        self.leap_second = Some(0);
+        self.first_minute = false;
+        self.clear_jumps();
+        Ok(())
+    }
+
     /// Returns if the current date/time is valid (date, time, DST are all `is_some()`).
     pub fn is_valid(&self) -> bool {
         self.dst.is_some()
@@ -202,9 +612,24 @@ impl RadioDateTimeUtils {
     /// Add one minute to the current date and time, return if the operation succeeded.
     ///
     /// * Years are limited to 2 digits, so this function wraps after 100 years.
+    ///   Use `get_full_year()` to obtain the century.
     pub fn add_minute(&mut self) -> bool {
-        if !self.is_valid()
-        {
+        self.add_minute_dst(true)
+    }
+
+    /// Add one minute like `add_minute()`, but without changing the hour for an announced
+    /// DST change. This is meant for stations which transmit UTC.
+    pub fn add_minute_utc(&mut self) -> bool {
+        self.add_minute_dst(false)
+    }
+
+    /// Add one minute, return if the operation succeeded.
+    ///
+    /// # Arguments
+    /// * `dst_change` - change the hour at the end of the hour in which a DST change is
+    ///   announced.
+    fn add_minute_dst(&mut self, dst_change: bool) -> bool {
+        if !self.is_valid() {
             return false;
         }
         let mut s_minute = self.minute.unwrap();
@@ -216,7 +641,7 @@ impl RadioDateTimeUtils {
         s_minute += 1;
         if s_minute == 60 {
             s_minute = 0;
-            if (self.dst.unwrap() & DST_ANNOUNCED) != 0 {
+            if dst_change && (self.dst.unwrap() & DST_ANNOUNCED) != 0 {
                 if (self.dst.unwrap() & DST_SUMMER) != 0 {
                     s_hour -= 1; // changing to winter
                 } else {
@@ -261,13 +686,32 @@ impl RadioDateTimeUtils {
     /// * `valid` - extra validation to pass.
     /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
     pub fn set_year(&mut self, value: Option<u8>, valid: bool, check_jump: bool) {
-        let year = if value.is_some() && (0..=99).contains(&value.unwrap()) && valid {
-            value
-        } else {
-            self.year
-        };
+        self.update_year(value, valid, check_jump);
+    }
+
+    /// Set the year value like `set_year()`, return if the new value was accepted.
+    fn update_year(&mut self, value: Option<u8>, valid: bool, check_jump: bool) -> bool {
+        let accepted = value.is_some() && (0..=99).contains(&value.unwrap()) && valid;
+        let year = if accepted { value } else { self.year };
         self.jump_year = check_jump && year.is_some() && self.year.is_some() && year != self.year;
         self.year = year;
+        accepted
+    }
+
+    /// Set the year value like `set_year()`, but return why the value was not stored.
+    ///
+    /// # Arguments
+    /// * `value` - the new year value
+    /// * `valid` - extra validation to pass, a ParityMismatch error otherwise.
+    /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
+    pub fn try_set_year(
+        &mut self,
+        value: u8,
+        valid: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        let accepted = self.update_year(Some(value), valid, check_jump);
+        set_result(valid, accepted)
     }
 
     /// Set the month value, valid values are 1 through 12.
@@ -277,14 +721,33 @@ impl RadioDateTimeUtils {
     /// * `valid` - extra validation to pass.
     /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
     pub fn set_month(&mut self, value: Option<u8>, valid: bool, check_jump: bool) {
-        let month = if value.is_some() && (1..=12).contains(&value.unwrap()) && valid {
-            value
-        } else {
-            self.month
-        };
+        self.update_month(value, valid, check_jump);
+    }
+
+    /// Set the month value like `set_month()`, return if the new value was accepted.
+    fn update_month(&mut self, value: Option<u8>, valid: bool, check_jump: bool) -> bool {
+        let accepted = value.is_some() && (1..=12).contains(&value.unwrap()) && valid;
+        let month = if accepted { value } else { self.month };
         self.jump_month =
             check_jump && month.is_some() && self.month.is_some() && month != self.month;
         self.month = month;
+        accepted
+    }
+
+    /// Set the month value like `set_month()`, but return why the value was not stored.
+    ///
+    /// # Arguments
+    /// * `value` - the new month value
+    /// * `valid` - extra validation to pass, a ParityMismatch error otherwise.
+    /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
+    pub fn try_set_month(
+        &mut self,
+        value: u8,
+        valid: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        let accepted = self.update_month(Some(value), valid, check_jump);
+        set_result(valid, accepted)
     }
 
     /// Set the day-of-week value, valid values are 0/1 through 6/7, depending on how this
@@ -295,17 +758,35 @@ impl RadioDateTimeUtils {
     /// * `valid` - extra validation to pass.
     /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
     pub fn set_weekday(&mut self, value: Option<u8>, valid: bool, check_jump: bool) {
-        let weekday = if value.is_some()
+        self.update_weekday(value, valid, check_jump);
+    }
+
+    /// Set the weekday value like `set_weekday()`, return if the new value was accepted.
+    fn update_weekday(&mut self, value: Option<u8>, valid: bool, check_jump: bool) -> bool {
+        let accepted = value.is_some()
             && (self.min_weekday..=self.max_weekday).contains(&value.unwrap())
-            && valid
-        {
-            value
-        } else {
-            self.weekday
-        };
+            && valid;
+        let weekday = if accepted { value } else { self.weekday };
         self.jump_weekday =
             check_jump && weekday.is_some() && self.weekday.is_some() && weekday != self.weekday;
         self.weekday = weekday;
+        accepted
+    }
+
+    /// Set the day-of-week value like `set_weekday()`, but return why the value was not stored.
+    ///
+    /// # Arguments
+    /// * `value` - the new day-of-week value
+    /// * `valid` - extra validation to pass, a ParityMismatch error otherwise.
+    /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
+    pub fn try_set_weekday(
+        &mut self,
+        value: u8,
+        valid: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        let accepted = self.update_weekday(Some(value), valid, check_jump);
+        set_result(valid, accepted)
     }
 
     /// Set the day-in-month value, valid values are 1 through the last day of that month.
@@ -318,17 +799,35 @@ impl RadioDateTimeUtils {
     /// * `valid` - extra validation to pass.
     /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
     pub fn set_day(&mut self, value: Option<u8>, valid: bool, check_jump: bool) {
-        let day = if value.is_some()
+        self.update_day(value, valid, check_jump);
+    }
+
+    /// Set the day value like `set_day()`, return if the new value was accepted.
+    fn update_day(&mut self, value: Option<u8>, valid: bool, check_jump: bool) -> bool {
+        let accepted = value.is_some()
             && self.last_day(value.unwrap()).is_some()
             && (1..=self.last_day(value.unwrap()).unwrap()).contains(&value.unwrap())
-            && valid
-        {
-            value
-        } else {
-            self.day
-        };
+            && valid;
+        let day = if accepted { value } else { self.day };
         self.jump_day = check_jump && day.is_some() && self.day.is_some() && day != self.day;
         self.day = day;
+        accepted
+    }
+
+    /// Set the day-in-month value like `set_day()`, but return why the value was not stored.
+    ///
+    /// # Arguments
+    /// * `value` - the new day-in-month value
+    /// * `valid` - extra validation to pass, a ParityMismatch error otherwise.
+    /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
+    pub fn try_set_day(
+        &mut self,
+        value: u8,
+        valid: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        let accepted = self.update_day(Some(value), valid, check_jump);
+        set_result(valid, accepted)
     }
 
     /// Set the hour value, valid values are 0 through 23.
@@ -338,13 +837,32 @@ impl RadioDateTimeUtils {
     /// * `valid` - extra validation to pass.
     /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
     pub fn set_hour(&mut self, value: Option<u8>, valid: bool, check_jump: bool) {
-        let hour = if value.is_some() && (0..=23).contains(&value.unwrap()) && valid {
-            value
-        } else {
-            self.hour
-        };
+        self.update_hour(value, valid, check_jump);
+    }
+
+    /// Set the hour value like `set_hour()`, return if the new value was accepted.
+    fn update_hour(&mut self, value: Option<u8>, valid: bool, check_jump: bool) -> bool {
+        let accepted = value.is_some() && (0..=23).contains(&value.unwrap()) && valid;
+        let hour = if accepted { value } else { self.hour };
         self.jump_hour = check_jump && hour.is_some() && self.hour.is_some() && hour != self.hour;
         self.hour = hour;
+        accepted
+    }
+
+    /// Set the hour value like `set_hour()`, but return why the value was not stored.
+    ///
+    /// # Arguments
+    /// * `value` - the new hour value
+    /// * `valid` - extra validation to pass, a ParityMismatch error otherwise.
+    /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
+    pub fn try_set_hour(
+        &mut self,
+        value: u8,
+        valid: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        let accepted = self.update_hour(Some(value), valid, check_jump);
+        set_result(valid, accepted)
     }
 
     /// Set the minute value, valid values are 0 through 59.
@@ -354,14 +872,33 @@ impl RadioDateTimeUtils {
     /// * `valid` - extra validation to pass.
     /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
     pub fn set_minute(&mut self, value: Option<u8>, valid: bool, check_jump: bool) {
-        let minute = if value.is_some() && (0..=59).contains(&value.unwrap()) && valid {
-            value
-        } else {
-            self.minute
-        };
+        self.update_minute(value, valid, check_jump);
+    }
+
+    /// Set the minute value like `set_minute()`, return if the new value was accepted.
+    fn update_minute(&mut self, value: Option<u8>, valid: bool, check_jump: bool) -> bool {
+        let accepted = value.is_some() && (0..=59).contains(&value.unwrap()) && valid;
+        let minute = if accepted { value } else { self.minute };
         self.jump_minute =
             check_jump && minute.is_some() && self.minute.is_some() && minute != self.minute;
         self.minute = minute;
+        accepted
+    }
+
+    /// Set the minute value like `set_minute()`, but return why the value was not stored.
+    ///
+    /// # Arguments
+    /// * `value` - the new minute value
+    /// * `valid` - extra validation to pass, a ParityMismatch error otherwise.
+    /// * `check_jump` - check if the value has jumped unexpectedly compared to `add_minute()`.
+    pub fn try_set_minute(
+        &mut self,
+        value: u8,
+        valid: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        let accepted = self.update_minute(Some(value), valid, check_jump);
+        set_result(valid, accepted)
     }
 
     /// Set the DST mask value, both the actual value and any information on transitions.
@@ -369,8 +906,8 @@ impl RadioDateTimeUtils {
     /// # Arguments
     /// * `value` - the new DST value. None or unannounced changes keep the old value.
     /// * `announce` - if any announcement is made on a transition. The history of this
-    ///                value of the last hour (or part thereof if started later) is kept
-    ///                to compensate for spurious True values.
+    ///   value of the last hour (or part thereof if started later) is kept
+    ///   to compensate for spurious True values.
     /// * `check_jump` - check if the value changed unexpectedly.
     pub fn set_dst(&mut self, value: Option<bool>, announce: Option<bool>, check_jump: bool) {
         if value.is_none() || announce.is_none() {
@@ -421,35 +958,103 @@ impl RadioDateTimeUtils {
         self.first_minute = false;
     }
 
-    /// Set the leap second value.
+    /// Set the DST mask value like `set_dst()`, but return an OutOfRange error if the
+    /// new DST value was not accepted because its change was not announced.
     ///
     /// # Arguments
-    /// * `announce` - if any announcement is made on a positive leap second. The history
-    ///                of this value of the last hour (or part thereof if started later) is
-    ///                kept to compensate for spurious Some(True) values.
-    /// * `minute_length` - the length of the decoded minute in seconds.
+    /// * `value` - the new DST value
+    /// * `announce` - if any announcement is made on a transition
+    /// * `check_jump` - check if the value changed unexpectedly.
+    pub fn try_set_dst(
+        &mut self,
+        value: bool,
+        announce: bool,
+        check_jump: bool,
+    ) -> Result<(), DecodeError> {
+        self.set_dst(Some(value), Some(announce), check_jump);
+        if (self.dst.unwrap() & DST_SUMMER != 0) == value {
+            Ok(())
+        } else {
+            Err(DecodeError::OutOfRange)
+        }
+    }
+
+    /// Set the leap second value for stations which do not transmit its sign.
+    ///
+    /// The sign follows from the length of the minute in which the leap second is
+    /// processed: 61 seconds for a positive and 59 seconds for a negative leap second.
+    /// Use `set_signed_leap_second()` for stations which announce the sign.
+    ///
+    /// # Arguments
+    /// * `announce` - if any announcement is made on a leap second. The history
+    ///   of this value of the last hour (or part thereof if started later) is
+    ///   kept to compensate for spurious Some(True) values.
+    /// * `minute_length` - the length of the decoded minute in seconds, 59 through 61.
     pub fn set_leap_second(&mut self, announce: Option<bool>, minute_length: u8) {
-        if announce.is_none() || !(60..=61).contains(&minute_length) {
+        if announce.is_some()
+            && minute_length == 59
+            && self.minute == Some(0)
+            && self.leap_second.is_some_and(|l| l & LEAP_ANNOUNCED != 0)
+        {
+            // A shortened minute at the hour can only be a negative leap second:
+            self.leap_second = Some(self.leap_second.unwrap() | LEAP_NEGATIVE);
+        }
+        self.set_signed_leap_second(announce, Some(false), minute_length);
+    }
+
+    /// Set the leap second value, including its sign.
+    ///
+    /// A positive leap second is expected to result in a 61-second minute and a
+    /// negative one in a 59-second minute, otherwise LEAP_MISSING is set.
+    ///
+    /// # Arguments
+    /// * `announce` - if any announcement is made on a leap second. The history
+    ///   of this value of the last hour (or part thereof if started later) is
+    ///   kept to compensate for spurious Some(True) values.
+    /// * `negative` - if the announced leap second is negative. Like `announce`, the
+    ///   history of this value is kept, it is only counted if `announce` is Some(true).
+    /// * `minute_length` - the length of the decoded minute in seconds, 59 through 61.
+    pub fn set_signed_leap_second(
+        &mut self,
+        announce: Option<bool>,
+        negative: Option<bool>,
+        minute_length: u8,
+    ) {
+        if announce.is_none() || !(59..=61).contains(&minute_length) {
             return;
         }
         if self.leap_second.is_none() {
             self.leap_second = Some(0);
         }
-        // Determine if a leap second is announced:
+        // Determine if a leap second is announced, and its sign:
         if announce == Some(true) {
             self.leap_second_count += 1;
+            if negative == Some(true) {
+                self.leap_second_negative_count += 1;
+            }
         }
         if self.minute.is_some() && self.minute.unwrap() > 0 {
             if 2 * self.leap_second_count > self.minutes_running {
                 self.leap_second = Some(self.leap_second.unwrap() | LEAP_ANNOUNCED);
+                if 2 * self.leap_second_negative_count > self.leap_second_count {
+                    self.leap_second = Some(self.leap_second.unwrap() | LEAP_NEGATIVE);
+                } else {
+                    self.leap_second = Some(self.leap_second.unwrap() & !LEAP_NEGATIVE);
+                }
             } else {
-                self.leap_second = Some(self.leap_second.unwrap() & !LEAP_ANNOUNCED);
+                self.leap_second =
+                    Some(self.leap_second.unwrap() & !LEAP_ANNOUNCED & !LEAP_NEGATIVE);
             }
         }
         // Process possible leap second:
         if self.minute == Some(0) && (self.leap_second.unwrap() & LEAP_ANNOUNCED) != 0 {
             self.leap_second = Some(self.leap_second.unwrap() | LEAP_PROCESSED);
-            if minute_length == 60 {
+            let expected_length = if self.leap_second.unwrap() & LEAP_NEGATIVE != 0 {
+                59
+            } else {
+                61
+            };
+            if minute_length != expected_length {
                 // Leap second processed, but missing:
                 self.leap_second = Some(self.leap_second.unwrap() | LEAP_MISSING);
             } else {
@@ -458,14 +1063,36 @@ impl RadioDateTimeUtils {
             }
         } else if self.minute.is_some() {
             self.leap_second = Some(self.leap_second.unwrap() & !LEAP_PROCESSED & !LEAP_MISSING);
+            if self.minute == Some(0) {
+                self.leap_second = Some(self.leap_second.unwrap() & !LEAP_NEGATIVE);
+            }
         }
         // Always reset announcement at the hour:
         if self.minute == Some(0) {
             self.leap_second = Some(self.leap_second.unwrap() & !LEAP_ANNOUNCED);
             self.leap_second_count = 0;
+            self.leap_second_negative_count = 0;
         }
     }
 
+    /// Set the leap second value like `set_leap_second()`, but return an OutOfRange error
+    /// if the minute length is invalid.
+    ///
+    /// # Arguments
+    /// * `announce` - if any announcement is made on a leap second
+    /// * `minute_length` - the length of the decoded minute in seconds, 59 through 61.
+    pub fn try_set_leap_second(
+        &mut self,
+        announce: bool,
+        minute_length: u8,
+    ) -> Result<(), DecodeError> {
+        if !(59..=61).contains(&minute_length) {
+            return Err(DecodeError::OutOfRange);
+        }
+        self.set_leap_second(Some(announce), minute_length);
+        Ok(())
+    }
+
     /// Bump the internal minute counter needed for set_dst() and set_leap_second()
     ///
     /// The code above this library must call this function, as this library cannot
@@ -477,6 +1104,15 @@ impl RadioDateTimeUtils {
         }
     }
 
+    /// Return the numeric value of Sunday, as passed to `new()`.
+    fn sunday(&self) -> u8 {
+        if self.max_weekday == 7 {
+            7
+        } else {
+            0
+        }
+    }
+
     /// Return the last calendar day of the current date, or None in case of error.
     ///
     /// # Arguments
@@ -494,7 +1130,13 @@ impl RadioDateTimeUtils {
         let s_month = self.month.unwrap();
         let s_weekday = self.weekday.unwrap();
         if s_month == 2 {
-            if (s_year != 0 && s_year % 4 == 0)
+            if self.pivot_year.is_some() {
+                if radio_datetime_helpers::is_leap_year(self.get_full_year().unwrap()) {
+                    Some(29)
+                } else {
+                    Some(28)
+                }
+            } else if (s_year != 0 && s_year.is_multiple_of(4))
                 || (s_year == 0 && RadioDateTimeUtils::is_leap_century(day, s_weekday))
             {
                 Some(29)
@@ -531,10 +1173,51 @@ impl RadioDateTimeUtils {
     }
 }
 
+/// Returns the outcome of a `try_set_*()` call from the result of the matching `update_*()`.
+///
+/// # Arguments
+/// * `valid` - extra validation passed to `set_*()`
+/// * `accepted` - if the new value was accepted
+fn set_result(valid: bool, accepted: bool) -> Result<(), DecodeError> {
+    if !valid {
+        Err(DecodeError::ParityMismatch)
+    } else if !accepted {
+        Err(DecodeError::OutOfRange)
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
+    /// Returns a valid date/time at the given date and time, with the day of the week
+    /// calculated from the date and no announcements. Shared by the decoder tests.
+    pub(crate) fn rdt_at(
+        sunday: u8,
+        (year, month, day): (u8, u8, u8),
+        (hour, minute): (u8, u8),
+        summer: bool,
+    ) -> RadioDateTimeUtils {
+        let mut rdt = RadioDateTimeUtils::new(sunday);
+        rdt.set_year(Some(year), true, false);
+        rdt.set_month(Some(month), true, false);
+        rdt.set_weekday(
+            radio_datetime_helpers::get_weekday(year, month, day, sunday),
+            true,
+            false,
+        );
+        // day must be set *after* year, month, and weekday
+        rdt.set_day(Some(day), true, false);
+        rdt.set_hour(Some(hour), true, false);
+        rdt.set_minute(Some(minute), true, false);
+        rdt.set_dst(Some(summer), Some(false), false);
+        rdt.set_leap_second(Some(false), 60);
+        rdt
+    }
+
     #[test]
     fn test_increase_second_regular() {
         let mut second = 54;
@@ -545,6 +1228,20 @@ mod tests {
         assert_eq!(second, 55);
     }
     #[test]
+    fn test_increase_second_negative_leap_second() {
+        let mut second = 57;
+        assert_eq!(
+            RadioDateTimeUtils::increase_second(&mut second, false, 59),
+            true
+        );
+        assert_eq!(second, 58);
+        assert_eq!(
+            RadioDateTimeUtils::increase_second(&mut second, false, 59),
+            false
+        );
+        assert_eq!(second, 0);
+    }
+    #[test]
     fn test_increase_second_new_minute() {
         let mut second = 54; // can be less than minute_length-1 during the first partial minute
         assert_eq!(
@@ -933,6 +1630,158 @@ mod tests {
         assert_eq!(rdt.jump_minute, true);
     }
 
+    #[test]
+    fn test_try_set_minute_ok() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(rdt.try_set_minute(42, true, false), Ok(()));
+        assert_eq!(rdt.minute, Some(42));
+    }
+    #[test]
+    fn test_try_set_minute_parity() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(
+            rdt.try_set_minute(42, false, false),
+            Err(DecodeError::ParityMismatch)
+        );
+        assert_eq!(rdt.minute, None);
+    }
+    #[test]
+    fn test_try_set_hour_out_of_range() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.hour = Some(3);
+        assert_eq!(
+            rdt.try_set_hour(24, true, true),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(rdt.hour, Some(3));
+        assert_eq!(rdt.jump_hour, false);
+    }
+    #[test]
+    fn test_try_set_date() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(rdt.try_set_year(23, true, false), Ok(()));
+        assert_eq!(rdt.try_set_month(2, true, false), Ok(()));
+        assert_eq!(
+            rdt.try_set_weekday(7, true, false),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(
+            rdt.try_set_day(28, true, false),
+            Err(DecodeError::OutOfRange)
+        ); // no weekday
+        assert_eq!(rdt.try_set_weekday(2, true, false), Ok(()));
+        assert_eq!(
+            rdt.try_set_day(29, true, false),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(rdt.try_set_day(28, true, false), Ok(()));
+        assert_eq!(rdt.day, Some(28));
+    }
+    #[test]
+    fn test_try_set_day_rejected_same_value() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.year = Some(24);
+        rdt.month = Some(2);
+        rdt.weekday = Some(4);
+        rdt.day = Some(29);
+        // 2023-02-29 does not exist, even though the day is unchanged:
+        rdt.year = Some(23);
+        assert_eq!(
+            rdt.try_set_day(29, true, false),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(rdt.day, Some(29));
+    }
+    #[test]
+    fn test_try_set_dst() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.minute = Some(10);
+        assert_eq!(rdt.try_set_dst(true, false, false), Ok(())); // first minute
+        assert_eq!(
+            rdt.try_set_dst(false, false, true),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(rdt.dst, Some(DST_SUMMER | DST_JUMP));
+    }
+    #[test]
+    fn test_try_set_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.minute = Some(10);
+        assert_eq!(
+            rdt.try_set_leap_second(false, 58),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(
+            rdt.try_set_leap_second(false, 62),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(rdt.leap_second, None);
+        assert_eq!(rdt.try_set_leap_second(false, 59), Ok(()));
+        assert_eq!(rdt.leap_second, Some(0));
+    }
+
+    #[test]
+    fn test_get_dst_status() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(rdt.get_dst_status(), None);
+        rdt.dst = Some(DST_SUMMER | DST_ANNOUNCED);
+        let dst = rdt.get_dst_status().unwrap();
+        assert!(dst.is_summer());
+        assert!(dst.change_announced());
+        assert!(!dst.change_processed());
+        assert!(!dst.jumped());
+        assert_eq!(dst.bits(), DST_SUMMER | DST_ANNOUNCED);
+        assert_eq!(dst.to_string(), "summer, change announced");
+        assert_eq!(
+            format!("{dst:?}"),
+            "DstStatus { summer: true, announced: true, processed: false, jump: false }"
+        );
+        assert_eq!(DstStatus::new(DST_JUMP).to_string(), "winter, jump");
+    }
+    #[test]
+    fn test_get_leap_second_status() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(rdt.get_leap_second_status(), None);
+        rdt.leap_second = Some(LEAP_PROCESSED | LEAP_MISSING);
+        let leap_second = rdt.get_leap_second_status().unwrap();
+        assert!(!leap_second.leap_announced());
+        assert!(leap_second.leap_processed());
+        assert!(leap_second.leap_missing());
+        assert_eq!(leap_second.to_string(), "leap second missing");
+        assert_eq!(
+            format!("{leap_second:?}"),
+            "LeapSecondStatus { announced: false, processed: true, missing: true, negative: false }"
+        );
+        assert_eq!(LeapSecondStatus::new(0).to_string(), "no leap second");
+        assert_eq!(
+            LeapSecondStatus::new(LEAP_ANNOUNCED | LEAP_NEGATIVE).to_string(),
+            "negative leap second announced"
+        );
+    }
+
+    #[test]
+    fn test_decode_error_field_status() {
+        assert_eq!(
+            FieldStatus::from(DecodeError::MissingBit { index: 3 }),
+            FieldStatus::Missing
+        );
+        assert_eq!(
+            FieldStatus::from(DecodeError::ParityMismatch),
+            FieldStatus::BadParity
+        );
+        assert_eq!(
+            FieldStatus::from(DecodeError::BadNibble),
+            FieldStatus::Invalid
+        );
+    }
+    #[test]
+    fn test_decode_error_display() {
+        assert_eq!(
+            DecodeError::MissingBit { index: 28 }.to_string(),
+            "missing bit at position 28"
+        );
+    }
+
     #[test]
     fn test_last_day7_regular() {
         let mut dcf77 = RadioDateTimeUtils::new(7);
@@ -1021,6 +1870,109 @@ mod tests {
         assert_eq!(msf.last_day(6), None); // invalid input, None-day 00-02-06
     }
 
+    #[test]
+    fn test_last_day7_pivot_century_regular() {
+        let mut dcf77 = RadioDateTimeUtils::new(7);
+        dcf77.set_pivot_year(Some(2050));
+        dcf77.year = Some(0);
+        dcf77.month = Some(2);
+        dcf77.weekday = Some(2); // bogus weekday, the pivot year decides
+        assert_eq!(dcf77.last_day(1), Some(28)); // 2100-02-01
+    }
+    #[test]
+    fn test_last_day7_pivot_century_leap() {
+        let mut dcf77 = RadioDateTimeUtils::new(7);
+        dcf77.set_pivot_year(Some(2000));
+        dcf77.year = Some(0);
+        dcf77.month = Some(2);
+        dcf77.weekday = Some(1); // bogus weekday, the pivot year decides
+        assert_eq!(dcf77.last_day(1), Some(29)); // 2000-02-01
+    }
+
+    #[test]
+    fn test_get_full_year_none() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        assert_eq!(rdt.get_full_year(), None);
+        rdt.year = Some(24);
+        // no pivot year and no full date to derive the century from
+        assert_eq!(rdt.get_full_year(), None);
+    }
+    #[test]
+    fn test_get_full_year_pivot() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_pivot_year(Some(1970));
+        rdt.year = Some(99);
+        assert_eq!(rdt.get_full_year(), Some(1999));
+        rdt.year = Some(0);
+        assert_eq!(rdt.get_full_year(), Some(2000));
+        rdt.year = Some(70);
+        assert_eq!(rdt.get_full_year(), Some(1970));
+    }
+    #[test]
+    fn test_get_full_year_weekday_20th_century() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.year = Some(99);
+        rdt.month = Some(12);
+        rdt.day = Some(31);
+        rdt.weekday = Some(5); // Friday 1999-12-31
+        assert_eq!(rdt.get_full_year(), Some(1999));
+    }
+    #[test]
+    fn test_get_full_year_weekday_21st_century() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.year = Some(0);
+        rdt.month = Some(2);
+        rdt.day = Some(29);
+        rdt.weekday = Some(2); // Tuesday 2000-02-29
+        assert_eq!(rdt.get_full_year(), Some(2000));
+    }
+    #[test]
+    fn test_get_full_year_weekday_22nd_century() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.year = Some(0);
+        rdt.month = Some(3);
+        rdt.day = Some(1);
+        rdt.weekday = Some(1); // Monday 2100-03-01
+        assert_eq!(rdt.get_full_year(), Some(2100));
+    }
+    #[test]
+    fn test_get_full_year_weekday_not_sunday() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.year = Some(20);
+        rdt.month = Some(1);
+        rdt.day = Some(25);
+        rdt.weekday = Some(6); // Saturday 2020-01-25, but Sunday 1920-01-25
+        assert_eq!(rdt.get_full_year(), Some(2020));
+        rdt.weekday = Some(7);
+        assert_eq!(rdt.get_full_year(), Some(1920));
+    }
+    #[test]
+    fn test_get_full_year_weekday_sunday0() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.year = Some(20);
+        rdt.month = Some(1);
+        rdt.day = Some(25);
+        rdt.weekday = Some(0); // Sunday 1920-01-25
+        assert_eq!(rdt.get_full_year(), Some(1920));
+        rdt.weekday = Some(6);
+        assert_eq!(rdt.get_full_year(), Some(2020));
+    }
+    #[test]
+    fn continue_get_full_year_add_minute_century_flip() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_pivot_year(Some(1970));
+        rdt.minute = Some(59);
+        rdt.hour = Some(23);
+        rdt.day = Some(31);
+        rdt.month = Some(12);
+        rdt.year = Some(99);
+        rdt.weekday = Some(5); // 1999-12-31 is a Friday
+        rdt.dst = Some(0);
+        assert_eq!(rdt.get_full_year(), Some(1999));
+        assert_eq!(rdt.add_minute(), true);
+        assert_eq!(rdt.get_full_year(), Some(2000));
+    }
+
     #[test]
     fn test_dst_some_starting_no_dst_no_announcement_no_jump() {
         let mut rdt = RadioDateTimeUtils::new(0);
@@ -1190,6 +2142,29 @@ mod tests {
         assert_eq!(rdt.leap_second_count, 0);
     }
     #[test]
+    fn test_leap_second_short_minute() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.minute = Some(0);
+        for _ in 0..59 {
+            rdt.minute = Some(rdt.minute.unwrap() + 1);
+            rdt.minutes_running += 1;
+            rdt.set_leap_second(Some(true), 60);
+        }
+        assert_eq!(rdt.leap_second, Some(LEAP_ANNOUNCED));
+        // The sign is only known from the length of the minute at the hour:
+        rdt.minute = Some(0);
+        rdt.set_leap_second(Some(false), 59);
+        assert_eq!(rdt.leap_second, Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+        assert_eq!(rdt.leap_second_count, 0);
+    }
+    #[test]
+    fn continue_leap_second_short_minute_not_announced() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.minute = Some(0);
+        rdt.set_leap_second(Some(false), 59);
+        assert_eq!(rdt.leap_second, Some(0)); // no flags
+    }
+    #[test]
     fn test_leap_second_starting_at_new_hour_no_announcement() {
         let mut rdt = RadioDateTimeUtils::new(7);
         // Simple initial minute update at top-of-hour, no announcement:
@@ -1291,6 +2266,69 @@ mod tests {
         assert_eq!(rdt.leap_second, Some(LEAP_PROCESSED | LEAP_MISSING));
         assert_eq!(rdt.leap_second_count, 1);
     }
+    #[test]
+    fn continue_negative_leap_second_present() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.minute = Some(0);
+        for _ in 0..12 {
+            rdt.minute = Some(rdt.minute.unwrap() + 1);
+            rdt.minutes_running += 1;
+            rdt.set_signed_leap_second(Some(true), Some(true), 60);
+        }
+        assert_eq!(rdt.leap_second, Some(LEAP_ANNOUNCED | LEAP_NEGATIVE));
+        assert_eq!(rdt.leap_second_negative_count, 12);
+        rdt.minute = Some(0);
+        rdt.set_signed_leap_second(Some(false), Some(false), 59);
+        // Top of hour, so announcement should be reset but the sign kept:
+        assert_eq!(rdt.leap_second, Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+        assert_eq!(rdt.leap_second_negative_count, 0);
+        rdt.minute = Some(1);
+        // New hour has started:
+        rdt.set_signed_leap_second(Some(false), Some(false), 60);
+        assert_eq!(rdt.leap_second, Some(0));
+    }
+    #[test]
+    fn continue_negative_leap_second_missing() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.minute = Some(0);
+        for _ in 0..12 {
+            rdt.minute = Some(rdt.minute.unwrap() + 1);
+            rdt.minutes_running += 1;
+            rdt.set_signed_leap_second(Some(true), Some(true), 60);
+        }
+        rdt.minute = Some(0);
+        rdt.set_signed_leap_second(Some(false), Some(false), 61 /* not 59 */);
+        assert_eq!(
+            rdt.leap_second,
+            Some(LEAP_PROCESSED | LEAP_MISSING | LEAP_NEGATIVE)
+        );
+    }
+    #[test]
+    fn continue_positive_leap_second_wrong_sign() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.minute = Some(0);
+        for _ in 0..12 {
+            rdt.minute = Some(rdt.minute.unwrap() + 1);
+            rdt.minutes_running += 1;
+            rdt.set_signed_leap_second(Some(true), Some(false), 60);
+        }
+        assert_eq!(rdt.leap_second, Some(LEAP_ANNOUNCED));
+        rdt.minute = Some(0);
+        rdt.set_signed_leap_second(Some(false), Some(false), 59 /* not 61 */);
+        assert_eq!(rdt.leap_second, Some(LEAP_PROCESSED | LEAP_MISSING));
+    }
+    #[test]
+    fn test_leap_second_negative_spurious_sign() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.minute = Some(0);
+        for i in 0..10 {
+            rdt.minute = Some(rdt.minute.unwrap() + 1);
+            rdt.minutes_running += 1;
+            rdt.set_signed_leap_second(Some(true), Some(i == 4), 60);
+        }
+        assert_eq!(rdt.leap_second, Some(LEAP_ANNOUNCED));
+        assert_eq!(rdt.leap_second_negative_count, 1);
+    }
 
     #[test]
     fn test_add_minute_invalid_input() {
@@ -1359,6 +2397,24 @@ mod tests {
         assert_eq!(rdt.weekday, Some(6));
     }
     #[test]
+    fn test_add_minute_utc_dst() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // The hour does not change for a station transmitting UTC:
+        rdt.minute = Some(59);
+        rdt.hour = Some(17);
+        rdt.day = Some(1);
+        rdt.month = Some(1);
+        rdt.year = Some(0);
+        rdt.weekday = Some(6); // 2000-01-01 is a Saturday
+        rdt.dst = Some(DST_ANNOUNCED);
+        assert_eq!(rdt.add_minute_utc(), true);
+        assert_eq!(rdt.dst, Some(DST_ANNOUNCED));
+        assert_eq!(rdt.minute, Some(0));
+        assert_eq!(rdt.hour, Some(18));
+        assert_eq!(rdt.day, Some(1));
+        assert_eq!(rdt.weekday, Some(6));
+    }
+    #[test]
     fn test_add_minute_msf_saturday_sunday() {
         let mut rdt = RadioDateTimeUtils::new(0);
         // Test flipping to min_weekday (MSF), Saturday 6 -> Sunday 0:
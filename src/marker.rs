@@ -0,0 +1,361 @@
+//! Detection of the minute boundary and the minute length from a stream of pulses.
+//!
+//! `MinuteDetector` keeps the second counter using `RadioDateTimeUtils::increase_second()`
+//! and determines `new_minute` and the minute length itself, so the caller does not need
+//! to know them beforehand. The minute length (59, 60, or 61 seconds) is the
+//! `minute_length` argument of the `decode_time()` functions, which pass it on to
+//! `set_leap_second()` or `set_signed_leap_second()`.
+
+use crate::pulse::Pulse;
+use crate::{RadioDateTimeUtils, BIT_BUFFER_SIZE};
+
+/// Maximum length of a minute in seconds, to allow for positive leap seconds.
+const MAX_MINUTE_LENGTH: u8 = 61;
+
+/// How a station marks the start of a minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerStyle {
+    /// no pulse in the last second of the minute (DCF77)
+    Gap,
+    /// a marker in the last second of the minute followed by one at second 0 (WWVB, JJY)
+    DoubleMarker,
+    /// a single long pulse at second 0 (MSF)
+    LongPulse,
+    /// a marker in the last second of the minute followed by no pulse at second 0 (WWV)
+    MarkerGap,
+}
+
+/// Finds the minute boundary and the minute length from classified pulses.
+#[derive(Clone, Copy, Debug)]
+pub struct MinuteDetector {
+    style: MarkerStyle,
+    second: u8,
+    minute_length: u8,
+    new_minute: bool,
+    minute_start: bool,
+    started: bool,
+    synced: bool,
+    last_marker: bool,
+    pending_minute: bool,
+}
+
+impl MinuteDetector {
+    /// Initialize a new MinuteDetector instance.
+    ///
+    /// # Arguments
+    /// * `style` - how the station marks the start of a minute
+    pub fn new(style: MarkerStyle) -> Self {
+        Self {
+            style,
+            second: 0,
+            minute_length: 0,
+            new_minute: false,
+            minute_start: false,
+            started: false,
+            synced: false,
+            last_marker: false,
+            pending_minute: false,
+        }
+    }
+
+    /// Get the marker style in use.
+    pub fn get_style(&self) -> MarkerStyle {
+        self.style
+    }
+
+    /// Get the second within the minute of the last pulse.
+    pub fn get_second(&self) -> u8 {
+        self.second
+    }
+
+    /// Get the length in seconds of the last completed minute, 59 through 61 for a
+    /// correctly received minute.
+    pub fn get_minute_length(&self) -> u8 {
+        self.minute_length
+    }
+
+    /// Returns if the last pulse completed a minute, so its length is final.
+    ///
+    /// With `MarkerStyle::DoubleMarker`, this happens at the first pulse after the
+    /// two (or three, with a leap second) consecutive markers, so at second 1 of the
+    /// new minute.
+    pub fn is_new_minute(&self) -> bool {
+        self.new_minute
+    }
+
+    /// Returns if the last pulse is the first one of a new minute, at second 0 or at
+    /// second 1 with `MarkerStyle::MarkerGap`.
+    pub fn is_minute_start(&self) -> bool {
+        self.minute_start
+    }
+
+    /// Handle a classified pulse.
+    ///
+    /// Once a minute of 59 through 61 seconds has been found, a gap or marker only starts
+    /// a new minute if that minute would have such a length, otherwise it counts as
+    /// missed seconds or a misread pulse.
+    ///
+    /// Returns if the second counter was increased/wrapped normally (true) or due to an
+    /// overflow (false), like `RadioDateTimeUtils::increase_second()`.
+    ///
+    /// # Arguments
+    /// * `seconds` - the number of seconds since the previous pulse, 0 for the first one
+    /// * `pulse` - the classification of this pulse
+    pub fn handle_pulse(&mut self, seconds: u32, pulse: Pulse) -> bool {
+        self.new_minute = false;
+        self.minute_start = false;
+        let mut ok = true;
+        match self.style {
+            MarkerStyle::Gap if seconds >= 2 && self.is_minute_end(seconds) => {
+                // no pulse in the last second of the minute
+                self.start_minute(self.second as u32 + seconds);
+                self.new_minute = true;
+            }
+            MarkerStyle::LongPulse
+                if pulse == Pulse::Marker && self.started && self.is_minute_end(seconds) =>
+            {
+                self.start_minute(self.second as u32 + seconds);
+                self.new_minute = true;
+            }
+            MarkerStyle::MarkerGap if seconds >= 2 && self.is_minute_end(seconds - 1) => {
+                // no pulse at second 0, so this pulse is at second 1
+                self.start_minute(self.second as u32 + seconds - 1);
+                RadioDateTimeUtils::increase_second(&mut self.second, false, MAX_MINUTE_LENGTH);
+                self.new_minute = true;
+            }
+            _ => {
+                if seconds > 1 {
+                    self.last_marker = false;
+                }
+                for _ in 0..seconds {
+                    ok &= RadioDateTimeUtils::increase_second(
+                        &mut self.second,
+                        false,
+                        MAX_MINUTE_LENGTH,
+                    );
+                }
+                if !ok {
+                    self.synced = false;
+                }
+            }
+        }
+        if self.style == MarkerStyle::DoubleMarker {
+            self.handle_double_marker(pulse);
+        }
+        self.last_marker = pulse == Pulse::Marker;
+        self.started = true;
+        ok
+    }
+
+    fn handle_double_marker(&mut self, pulse: Pulse) {
+        if pulse == Pulse::Marker {
+            if self.last_marker {
+                if self.pending_minute {
+                    // third marker in a row, the previous minute had a leap second
+                    self.minute_length += 1;
+                    RadioDateTimeUtils::increase_second(&mut self.second, true, MAX_MINUTE_LENGTH);
+                } else if self.is_minute_end(0) {
+                    // second marker in a row, this is second 0 of the new minute
+                    self.start_minute(self.second as u32);
+                    self.pending_minute = true;
+                }
+            }
+        } else if self.pending_minute {
+            self.pending_minute = false;
+            self.new_minute = true;
+        }
+    }
+
+    /// Returns if a minute would end `seconds` seconds after the last pulse.
+    ///
+    /// Before the first minute of a valid length is found, only a single missing pulse
+    /// (after a marker with `MarkerStyle::MarkerGap`), or a single pair of markers, is
+    /// accepted anywhere in the minute.
+    fn is_minute_end(&self, seconds: u32) -> bool {
+        if self.synced {
+            (59..=61).contains(&(self.second as u32 + seconds))
+        } else {
+            match self.style {
+                MarkerStyle::Gap => seconds == 2,
+                MarkerStyle::MarkerGap => seconds == 1 && self.last_marker,
+                MarkerStyle::DoubleMarker | MarkerStyle::LongPulse => true,
+            }
+        }
+    }
+
+    fn start_minute(&mut self, minute_length: u32) {
+        self.minute_length = minute_length.min(BIT_BUFFER_SIZE as u32) as u8;
+        self.synced = (59..=61).contains(&self.minute_length);
+        RadioDateTimeUtils::increase_second(&mut self.second, true, MAX_MINUTE_LENGTH);
+        self.minute_start = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed a minute of the given length, with markers at `markers`, starting with
+    /// `first_seconds` seconds since the previous pulse.
+    fn minute(detector: &mut MinuteDetector, length: u8, markers: &[u8], first_seconds: u32) {
+        for second in 0..length {
+            let pulse = if markers.contains(&second) {
+                Pulse::Marker
+            } else {
+                Pulse::Zero
+            };
+            detector.handle_pulse(if second == 0 { first_seconds } else { 1 }, pulse);
+        }
+    }
+
+    #[test]
+    fn test_gap() {
+        let mut detector = MinuteDetector::new(MarkerStyle::Gap);
+        minute(&mut detector, 59, &[], 0);
+        assert_eq!(detector.get_second(), 58);
+        assert!(detector.handle_pulse(2, Pulse::One));
+        assert!(detector.is_new_minute());
+        assert!(detector.is_minute_start());
+        assert_eq!(detector.get_second(), 0);
+        assert_eq!(detector.get_minute_length(), 60);
+        // leap second:
+        minute(&mut detector, 59, &[], 1);
+        assert!(!detector.is_new_minute());
+        detector.handle_pulse(2, Pulse::Zero);
+        assert_eq!(detector.get_minute_length(), 61);
+    }
+    #[test]
+    fn test_gap_missed_pulse() {
+        let mut detector = MinuteDetector::new(MarkerStyle::Gap);
+        minute(&mut detector, 59, &[], 0);
+        detector.handle_pulse(2, Pulse::Zero);
+        assert_eq!(detector.get_minute_length(), 60);
+        // missed pulse at second 30 is not the minute gap:
+        minute(&mut detector, 29, &[], 1);
+        assert!(detector.handle_pulse(2, Pulse::Zero));
+        assert!(!detector.is_new_minute());
+        assert_eq!(detector.get_second(), 31);
+        minute(&mut detector, 27, &[], 1);
+        assert_eq!(detector.get_second(), 58);
+        detector.handle_pulse(2, Pulse::Zero);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_minute_length(), 60);
+        // missed pulse at second 58 just before the minute gap:
+        minute(&mut detector, 57, &[], 1);
+        assert_eq!(detector.get_second(), 57);
+        detector.handle_pulse(3, Pulse::Zero);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_second(), 0);
+        assert_eq!(detector.get_minute_length(), 60);
+    }
+    #[test]
+    fn test_marker_gap() {
+        let mut detector = MinuteDetector::new(MarkerStyle::MarkerGap);
+        // a missing pulse without a marker before it is not the minute gap:
+        minute(&mut detector, 5, &[], 0);
+        detector.handle_pulse(2, Pulse::Zero);
+        assert!(!detector.is_new_minute());
+        assert_eq!(detector.get_second(), 6);
+        minute(&mut detector, 4, &[3], 1);
+        detector.handle_pulse(2, Pulse::Zero);
+        assert!(detector.is_new_minute());
+        assert!(detector.is_minute_start());
+        assert_eq!(detector.get_second(), 1);
+        assert_eq!(detector.get_minute_length(), 11);
+        // seconds 2 through 59, with the marker at second 59:
+        minute(&mut detector, 58, &[57], 1);
+        assert_eq!(detector.get_second(), 59);
+        assert!(!detector.is_new_minute());
+        detector.handle_pulse(2, Pulse::Zero);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_second(), 1);
+        assert_eq!(detector.get_minute_length(), 60);
+        // missed pulse at second 30 is not the minute gap:
+        minute(&mut detector, 28, &[], 1);
+        detector.handle_pulse(2, Pulse::Marker);
+        assert!(!detector.is_new_minute());
+        assert_eq!(detector.get_second(), 31);
+    }
+    #[test]
+    fn test_gap_overflow() {
+        let mut detector = MinuteDetector::new(MarkerStyle::Gap);
+        minute(&mut detector, 61, &[], 0);
+        assert_eq!(detector.get_second(), 60);
+        assert!(!detector.handle_pulse(1, Pulse::Zero));
+        assert_eq!(detector.get_second(), 0);
+        assert!(!detector.is_new_minute());
+    }
+    #[test]
+    fn test_double_marker() {
+        let markers = [0, 9, 19, 29, 39, 49, 59];
+        let mut detector = MinuteDetector::new(MarkerStyle::DoubleMarker);
+        minute(&mut detector, 60, &markers, 0);
+        detector.handle_pulse(1, Pulse::Marker);
+        assert!(detector.is_minute_start());
+        assert!(!detector.is_new_minute());
+        detector.handle_pulse(1, Pulse::One);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_second(), 1);
+        assert_eq!(detector.get_minute_length(), 60);
+        // the rest of a minute with a positive leap second:
+        for second in 2..60 {
+            let pulse = if markers.contains(&second) {
+                Pulse::Marker
+            } else {
+                Pulse::Zero
+            };
+            detector.handle_pulse(1, pulse);
+        }
+        // the marker at second 60 looks like the start of the next minute:
+        detector.handle_pulse(1, Pulse::Marker);
+        assert!(detector.is_minute_start());
+        assert_eq!(detector.get_minute_length(), 60);
+        assert_eq!(detector.get_second(), 0);
+        // until the third marker:
+        detector.handle_pulse(1, Pulse::Marker);
+        assert!(!detector.is_minute_start());
+        assert_eq!(detector.get_second(), 0);
+        detector.handle_pulse(1, Pulse::Zero);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_minute_length(), 61);
+    }
+    #[test]
+    fn test_double_marker_misread() {
+        let markers = [0, 9, 19, 29, 39, 49, 59];
+        let mut detector = MinuteDetector::new(MarkerStyle::DoubleMarker);
+        minute(&mut detector, 60, &markers, 0);
+        minute(&mut detector, 2, &[0], 1);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_minute_length(), 60);
+        // a marker misread next to the one at second 9:
+        minute(&mut detector, 9, &[7, 8], 1);
+        assert!(!detector.is_minute_start());
+        assert_eq!(detector.get_second(), 10);
+        minute(&mut detector, 49, &[8, 18, 28, 38, 48], 1);
+        assert_eq!(detector.get_second(), 59);
+        detector.handle_pulse(1, Pulse::Marker);
+        assert!(detector.is_minute_start());
+        detector.handle_pulse(1, Pulse::Zero);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_minute_length(), 60);
+    }
+    #[test]
+    fn test_long_pulse() {
+        let mut detector = MinuteDetector::new(MarkerStyle::LongPulse);
+        // first pulse is the marker, no minute to complete yet:
+        detector.handle_pulse(0, Pulse::Marker);
+        assert!(!detector.is_new_minute());
+        minute(&mut detector, 58, &[], 1);
+        assert!(!detector.is_new_minute());
+        // negative leap second:
+        detector.handle_pulse(1, Pulse::Marker);
+        assert!(detector.is_new_minute());
+        assert!(detector.is_minute_start());
+        assert_eq!(detector.get_minute_length(), 59);
+        minute(&mut detector, 59, &[], 1);
+        // missed pulse just before the marker:
+        detector.handle_pulse(2, Pulse::Marker);
+        assert!(detector.is_new_minute());
+        assert_eq!(detector.get_minute_length(), 61);
+    }
+}
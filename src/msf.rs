@@ -0,0 +1,436 @@
+//! Decoder for the time code of MSF (Anthorn, United Kingdom, 60 kHz).
+
+use crate::radio_datetime_helpers::{get_bcd_value, get_parity, set_bcd_value};
+use crate::utc::{get_minute_length, is_summer};
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, DST_ANNOUNCED, DST_PROCESSED, DST_SUMMER,
+};
+
+/// Bit buffer holding both the A and the B bit of each second.
+#[derive(Clone, Copy)]
+pub struct BitBuffer {
+    /// A bits, indexed by second
+    pub a: [Option<bool>; BIT_BUFFER_SIZE],
+    /// B bits, indexed by second
+    pub b: [Option<bool>; BIT_BUFFER_SIZE],
+}
+
+impl BitBuffer {
+    /// Initialize a new, empty, BitBuffer instance.
+    pub fn new() -> Self {
+        Self {
+            a: [None; BIT_BUFFER_SIZE],
+            b: [None; BIT_BUFFER_SIZE],
+        }
+    }
+}
+
+impl Default for BitBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-field outcome of decoding one MSF minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub dst: FieldStatus,
+    pub leap_second: FieldStatus,
+    /// DUT1 in units of 0.1 second, None if absent or inconsistent
+    pub dut1: Option<i8>,
+}
+
+/// Decode the bit buffer of one MSF minute into the given date/time instance.
+///
+/// The date/time is first advanced by one minute using `add_minute()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// In a minute with a positive (negative) leap second, bits 17A through 59A and their
+/// B counterparts are transmitted one second later (earlier), this is compensated for
+/// using `minute_length`.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(0)`
+/// * `bit_buffer` - the A and B bits of the minute, indexed by second
+/// * `minute_length` - the length of the decoded minute in seconds, 59 through 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &BitBuffer,
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    let added_minute = rdt.add_minute();
+    let check_jump = check_jump && added_minute;
+    let length_ok = (59..=61).contains(&minute_length);
+    // Position of bit 17 and later, which shift with a leap second:
+    let at = |idx: usize| {
+        if length_ok {
+            idx + minute_length as usize - 60
+        } else {
+            idx
+        }
+    };
+    let a = &bit_buffer.a;
+    let b = &bit_buffer.b;
+    // The end-of-minute marker is 01111110 in bits 52A through 59A:
+    let frame_ok = length_ok
+        && a[at(52)] == Some(false)
+        && a[at(53)..=at(58)].iter().all(|bit| *bit == Some(true))
+        && a[at(59)] == Some(false);
+
+    // All parities are odd:
+    let parity_year = get_parity(a, at(17), at(24), b[at(54)]);
+    let parity_date = get_parity(a, at(25), at(35), b[at(55)]);
+    let parity_weekday = get_parity(a, at(36), at(38), b[at(56)]);
+    let parity_time = get_parity(a, at(39), at(51), b[at(57)]);
+
+    let minute = get_bcd_value(a, at(51), at(45)).filter(|_| frame_ok);
+    rdt.set_minute(minute, parity_time == Some(true), check_jump);
+    let hour = get_bcd_value(a, at(44), at(39)).filter(|_| frame_ok);
+    rdt.set_hour(hour, parity_time == Some(true), check_jump);
+    let year = get_bcd_value(a, at(24), at(17)).filter(|_| frame_ok);
+    rdt.set_year(year, parity_year == Some(true), check_jump);
+    let month = get_bcd_value(a, at(29), at(25)).filter(|_| frame_ok);
+    rdt.set_month(month, parity_date == Some(true), check_jump);
+    let weekday = get_bcd_value(a, at(38), at(36)).filter(|_| frame_ok);
+    rdt.set_weekday(weekday, parity_weekday == Some(true), check_jump);
+    // day must be set *after* year, month, and weekday
+    let day = get_bcd_value(a, at(35), at(30)).filter(|_| frame_ok);
+    rdt.set_day(day, parity_date == Some(true), check_jump);
+
+    let dst = b[at(58)].filter(|_| frame_ok);
+    rdt.set_dst(dst, b[at(53)], check_jump);
+    // MSF does not announce leap seconds:
+    rdt.set_leap_second(Some(false), minute_length);
+    rdt.bump_minutes_running();
+
+    DecodeReport {
+        minute: FieldStatus::new(&a[at(45)..=at(51)], parity_time, minute, rdt.get_minute()),
+        hour: FieldStatus::new(&a[at(39)..=at(44)], parity_time, hour, rdt.get_hour()),
+        day: FieldStatus::new(&a[at(30)..=at(35)], parity_date, day, rdt.get_day()),
+        weekday: FieldStatus::new(
+            &a[at(36)..=at(38)],
+            parity_weekday,
+            weekday,
+            rdt.get_weekday(),
+        ),
+        month: FieldStatus::new(&a[at(25)..=at(29)], parity_date, month, rdt.get_month()),
+        year: FieldStatus::new(&a[at(17)..=at(24)], parity_year, year, rdt.get_year()),
+        dst: FieldStatus::new(
+            &[b[at(53)], b[at(58)]],
+            Some(true),
+            dst.map(|d| d as u8),
+            rdt.get_dst().map(|d| (d & DST_SUMMER != 0) as u8),
+        ),
+        leap_second: FieldStatus::new(
+            &[],
+            Some(true),
+            Some(minute_length).filter(|_| length_ok),
+            Some(minute_length),
+        ),
+        dut1: get_dut1(b, at(17)),
+    }
+}
+
+/// Encode the given date/time into the bit buffer of one MSF minute, the inverse of
+/// `decode_time()`.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid. DUT1 is encoded as 0, unused bits at the end are None.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<(BitBuffer, u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let minute = rdt.get_minute().unwrap();
+    let dst = rdt.get_dst().unwrap();
+    let minute_length = get_minute_length(rdt.get_leap_second().unwrap_or(0), minute);
+    let at = |idx: usize| idx + minute_length as usize - 60;
+    let mut bit_buffer = BitBuffer::new();
+    let a = &mut bit_buffer.a;
+    let b = &mut bit_buffer.b;
+    for idx in 0..minute_length as usize {
+        a[idx] = Some(false);
+        b[idx] = Some(false);
+    }
+    // The minute marker:
+    a[0] = Some(true);
+    b[0] = Some(true);
+    set_bcd_value(a, at(24), at(17), rdt.get_year().unwrap());
+    set_bcd_value(a, at(29), at(25), rdt.get_month().unwrap());
+    set_bcd_value(a, at(35), at(30), rdt.get_day().unwrap());
+    // Sunday is 0:
+    set_bcd_value(a, at(38), at(36), rdt.get_weekday().unwrap() % 7);
+    set_bcd_value(a, at(44), at(39), rdt.get_hour().unwrap());
+    set_bcd_value(a, at(51), at(45), minute);
+    for bit in &mut a[at(53)..=at(58)] {
+        *bit = Some(true);
+    }
+    // Announcements last until the end of the minute before the change:
+    b[at(53)] = Some(dst & DST_ANNOUNCED != 0 || minute == 0 && dst & DST_PROCESSED != 0);
+    // All parities are odd:
+    b[at(54)] = get_parity(a, at(17), at(24), Some(true));
+    b[at(55)] = get_parity(a, at(25), at(35), Some(true));
+    b[at(56)] = get_parity(a, at(36), at(38), Some(true));
+    b[at(57)] = get_parity(a, at(39), at(51), Some(true));
+    b[at(58)] = Some(is_summer(dst, minute));
+    Some((bit_buffer, minute_length))
+}
+
+/// Return DUT1 in units of 0.1 second from bits 1B through 16B, or None if the input is invalid.
+///
+/// Bits 1B through 8B count positive tenths, bits 9B through 16B count negative tenths.
+/// Bit 16B is not transmitted in a minute with a negative leap second.
+///
+/// # Arguments
+/// * `b` - buffer containing the B bits
+/// * `bit17` - position of bit 17B, which shifts with a leap second
+pub(crate) fn get_dut1(b: &[Option<bool>; BIT_BUFFER_SIZE], bit17: usize) -> Option<i8> {
+    let mut positive = 0;
+    let mut negative = 0;
+    for (idx, bit) in b.iter().enumerate().take(bit17.min(17)).skip(1) {
+        if (*bit)? {
+            if idx <= 8 {
+                positive += 1;
+            } else {
+                negative += 1;
+            }
+        }
+    }
+    if positive != 0 && negative != 0 {
+        None
+    } else {
+        Some(positive - negative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DST_ANNOUNCED;
+
+    // Saturday 2024-06-15 13:47 BST, DUT1 = +0.2 s
+    fn frame(minute_length: u8) -> BitBuffer {
+        let mut a = [Some(false); BIT_BUFFER_SIZE];
+        let mut b = [Some(false); BIT_BUFFER_SIZE];
+        a[0] = Some(true);
+        b[0] = Some(true);
+        b[1] = Some(true);
+        b[2] = Some(true);
+        // bits 17 and later in their regular positions, shifted below:
+        let mut rest_a = [Some(false); BIT_BUFFER_SIZE];
+        let mut rest_b = [Some(false); BIT_BUFFER_SIZE];
+        set_bcd_value(&mut rest_a, 24, 17, 24);
+        set_bcd_value(&mut rest_a, 29, 25, 6);
+        set_bcd_value(&mut rest_a, 35, 30, 15);
+        set_bcd_value(&mut rest_a, 38, 36, 6);
+        set_bcd_value(&mut rest_a, 44, 39, 13);
+        set_bcd_value(&mut rest_a, 51, 45, 47);
+        for bit in &mut rest_a[53..=58] {
+            *bit = Some(true);
+        }
+        // odd parity bits:
+        rest_b[54] = get_parity(&rest_a, 17, 24, Some(true));
+        rest_b[55] = get_parity(&rest_a, 25, 35, Some(true));
+        rest_b[56] = get_parity(&rest_a, 36, 38, Some(true));
+        rest_b[57] = get_parity(&rest_a, 39, 51, Some(true));
+        rest_b[58] = Some(true);
+        for idx in 17..=59 {
+            a[idx + minute_length as usize - 60] = rest_a[idx];
+            b[idx + minute_length as usize - 60] = rest_b[idx];
+        }
+        if minute_length == 61 {
+            // the inserted second
+            a[17] = Some(false);
+            b[17] = Some(false);
+        }
+        BitBuffer { a, b }
+    }
+
+    const ALL_OK: DecodeReport = DecodeReport {
+        minute: FieldStatus::Ok,
+        hour: FieldStatus::Ok,
+        day: FieldStatus::Ok,
+        weekday: FieldStatus::Ok,
+        month: FieldStatus::Ok,
+        year: FieldStatus::Ok,
+        dst: FieldStatus::Ok,
+        leap_second: FieldStatus::Ok,
+        dut1: Some(2),
+    };
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(decode_time(&mut rdt, &frame(60), 60, true), ALL_OK);
+        assert_eq!(rdt.get_minute(), Some(47));
+        assert_eq!(rdt.get_hour(), Some(13));
+        assert_eq!(rdt.get_day(), Some(15));
+        assert_eq!(rdt.get_weekday(), Some(6));
+        assert_eq!(rdt.get_month(), Some(6));
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+        assert_eq!(rdt.get_leap_second(), Some(0));
+        assert!(rdt.is_valid());
+    }
+    #[test]
+    fn test_decode_time_positive_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(decode_time(&mut rdt, &frame(61), 61, true), ALL_OK);
+        assert_eq!(rdt.get_minute(), Some(47));
+        assert_eq!(rdt.get_year(), Some(24));
+    }
+    #[test]
+    fn test_decode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(decode_time(&mut rdt, &frame(59), 59, true), ALL_OK);
+        assert_eq!(rdt.get_minute(), Some(47));
+        assert_eq!(rdt.get_year(), Some(24));
+    }
+    #[test]
+    fn test_decode_time_wrong_minute_length() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &frame(61), 60, true);
+        assert_eq!(report.minute, FieldStatus::Invalid);
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn test_decode_time_bad_minute_length() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &frame(60), 62, true);
+        assert_eq!(report.leap_second, FieldStatus::Invalid);
+        assert_eq!(rdt.get_leap_second(), None);
+    }
+    #[test]
+    fn continue_decode_time_jump() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(&mut rdt, &frame(60), 60, true);
+        assert_eq!(decode_time(&mut rdt, &frame(60), 60, true), ALL_OK);
+        assert!(rdt.get_jump_minute());
+    }
+    #[test]
+    fn test_decode_time_bad_parity_weekday() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(60);
+        buffer.b[56] = Some(!buffer.b[56].unwrap());
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.weekday, FieldStatus::BadParity);
+        assert_eq!(report.day, FieldStatus::Invalid); // needs the weekday
+        assert_eq!(report.month, FieldStatus::Ok);
+        assert_eq!(rdt.get_weekday(), None);
+    }
+    #[test]
+    fn test_decode_time_missing_time_bit() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(60);
+        buffer.a[40] = None;
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.hour, FieldStatus::Missing);
+        assert_eq!(report.minute, FieldStatus::Missing); // shares the time parity
+        assert_eq!(rdt.get_hour(), None);
+    }
+    #[test]
+    fn test_decode_time_dst_announced() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(60);
+        buffer.b[53] = Some(true);
+        decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_ANNOUNCED));
+    }
+
+    #[test]
+    fn test_get_dut1_negative() {
+        let mut b = [Some(false); BIT_BUFFER_SIZE];
+        b[9] = Some(true);
+        b[10] = Some(true);
+        b[11] = Some(true);
+        assert_eq!(get_dut1(&b, 17), Some(-3));
+    }
+    #[test]
+    fn test_get_dut1_negative_leap_second() {
+        let mut b = [Some(false); BIT_BUFFER_SIZE];
+        b[9] = Some(true);
+        // bit 17B, transmitted at second 16:
+        b[16] = Some(true);
+        assert_eq!(get_dut1(&b, 16), Some(-1));
+    }
+    #[test]
+    fn bad_get_dut1_both_signs() {
+        let mut b = [Some(false); BIT_BUFFER_SIZE];
+        b[1] = Some(true);
+        b[9] = Some(true);
+        assert_eq!(get_dut1(&b, 17), None);
+    }
+    #[test]
+    fn bad_get_dut1_none() {
+        let mut b = [Some(false); BIT_BUFFER_SIZE];
+        b[5] = None;
+        assert_eq!(get_dut1(&b, 17), None);
+    }
+
+    #[test]
+    fn test_encode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(&mut rdt, &frame(60), 60, false);
+        let (bit_buffer, minute_length) = encode_time(&rdt).unwrap();
+        assert_eq!(minute_length, 60);
+        let mut expected = frame(60);
+        // DUT1 is not encoded, and the buffer ends after second 59:
+        expected.b[1] = Some(false);
+        expected.b[2] = Some(false);
+        for idx in 60..BIT_BUFFER_SIZE {
+            expected.a[idx] = None;
+            expected.b[idx] = None;
+        }
+        assert_eq!(bit_buffer.a, expected.a);
+        assert_eq!(bit_buffer.b, expected.b);
+    }
+    #[test]
+    fn test_encode_time_round_trip() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(&mut rdt, &frame(60), 60, false);
+        for _ in 0..(24 * 60) {
+            let mut next_rdt = rdt;
+            assert!(next_rdt.add_minute());
+            let (bit_buffer, minute_length) = encode_time(&next_rdt).unwrap();
+            let report = decode_time(&mut rdt, &bit_buffer, minute_length, true);
+            assert_eq!(
+                report,
+                DecodeReport {
+                    dut1: Some(0),
+                    ..ALL_OK
+                }
+            );
+            assert!(!rdt.get_jump_minute() && !rdt.get_jump_day() && !rdt.get_jump_weekday());
+        }
+        // Sunday 2024-06-16 13:47 BST
+        assert_eq!(rdt.get_day(), Some(16));
+        assert_eq!(rdt.get_weekday(), Some(0));
+        assert_eq!(rdt.get_hour(), Some(13));
+        assert_eq!(rdt.get_minute(), Some(47));
+    }
+    #[test]
+    fn test_encode_time_dst_announced() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut bit_buffer = frame(60);
+        bit_buffer.b[53] = Some(true);
+        decode_time(&mut rdt, &bit_buffer, 60, false);
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_ANNOUNCED));
+        let (encoded, _) = encode_time(&rdt).unwrap();
+        assert_eq!(encoded.b[53], Some(true));
+        assert_eq!(encoded.b[58], Some(true));
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert!(encode_time(&RadioDateTimeUtils::new(0)).is_none());
+    }
+}
@@ -0,0 +1,220 @@
+//! Software phase-locked loop for the second ticks of a time signal.
+//!
+//! Edge timestamps taken in interrupt handlers jitter by several milliseconds.
+//! `SecondPll` locks onto the 1 Hz second ticks using integer arithmetic only, predicts
+//! the next tick, rejects glitches, and reports how many seconds passed at each tick so
+//! missed ticks can be filled in using `RadioDateTimeUtils::increase_second()`.
+
+use crate::radio_datetime_helpers::time_diff;
+
+/// Nominal length of a second in microseconds.
+const NOMINAL_PERIOD: u32 = 1_000_000;
+
+/// Maximum deviation of the period from the nominal one in microseconds, i.e. 1000 ppm.
+const MAX_PERIOD_DEVIATION: u32 = 1_000;
+
+/// Maximum phase error in microseconds of a tick to be used by the loop.
+const CAPTURE_WINDOW: u32 = 100_000;
+
+/// Maximum phase error in microseconds of a tick to count towards locking.
+const LOCK_THRESHOLD: u32 = 10_000;
+
+/// Number of consecutive ticks within `LOCK_THRESHOLD` needed to lock.
+const LOCK_COUNT: u8 = 4;
+
+/// Number of consecutive rejected ticks after which the loop unlocks.
+const MAX_REJECTS: u8 = 3;
+
+/// Divisor of the phase error for the phase correction.
+const PHASE_GAIN: i32 = 4;
+
+/// Divisor of the phase error for the period correction.
+const PERIOD_GAIN: i32 = 16;
+
+/// Phase-locked loop for second ticks.
+#[derive(Clone, Copy, Debug)]
+pub struct SecondPll {
+    period: u32,
+    last_tick: Option<u32>,
+    phase_error: i32,
+    good_ticks: u8,
+    rejects: u8,
+    locked: bool,
+}
+
+impl Default for SecondPll {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecondPll {
+    /// Initialize a new, unlocked, SecondPll instance with a nominal period of one second.
+    pub fn new() -> Self {
+        Self {
+            period: NOMINAL_PERIOD,
+            last_tick: None,
+            phase_error: 0,
+            good_ticks: 0,
+            rejects: 0,
+            locked: false,
+        }
+    }
+
+    /// Returns if the loop is locked onto the second ticks.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Get the estimated length of a second in microseconds.
+    pub fn get_period(&self) -> u32 {
+        self.period
+    }
+
+    /// Get the phase error of the last accepted tick in microseconds, positive if the
+    /// tick was late.
+    pub fn get_phase_error(&self) -> i32 {
+        self.phase_error
+    }
+
+    /// Get the filtered timestamp of the last tick in microseconds, if any.
+    pub fn get_last_tick(&self) -> Option<u32> {
+        self.last_tick
+    }
+
+    /// Get the predicted timestamp of the next tick in microseconds, if any.
+    pub fn get_next_tick(&self) -> Option<u32> {
+        self.last_tick.map(|t| t.wrapping_add(self.period))
+    }
+
+    /// Handle the timestamp of a second tick, i.e. the edge at the start of a pulse.
+    ///
+    /// Returns the number of seconds passed since the previous tick, so more than 1 if
+    /// ticks were missed, or 0 if the tick was rejected as a glitch. The first tick
+    /// returns 1.
+    ///
+    /// # Arguments
+    /// * `timestamp` - the time of the tick in microseconds
+    pub fn handle_tick(&mut self, timestamp: u32) -> u8 {
+        let Some(last_tick) = self.last_tick else {
+            self.last_tick = Some(timestamp);
+            return 1;
+        };
+        // Number of seconds since the previous tick, rounded:
+        let ticks = (time_diff(last_tick, timestamp) + self.period / 2) / self.period;
+        if ticks == 0 {
+            return 0;
+        }
+        let predicted = last_tick.wrapping_add(ticks.wrapping_mul(self.period));
+        let error = timestamp.wrapping_sub(predicted) as i32;
+        if error.unsigned_abs() > CAPTURE_WINDOW {
+            if self.locked && self.rejects < MAX_REJECTS {
+                self.rejects += 1;
+                return 0;
+            }
+            // (re-)acquire on this tick
+            self.locked = false;
+            self.good_ticks = 0;
+            self.rejects = 0;
+            self.phase_error = error;
+            self.last_tick = Some(timestamp);
+            return ticks.min(u8::MAX as u32) as u8;
+        }
+        self.rejects = 0;
+        self.phase_error = error;
+        self.last_tick = Some(predicted.wrapping_add_signed(error / PHASE_GAIN));
+        let period = self.period as i32 + error / (PERIOD_GAIN * ticks as i32);
+        self.period = (period as u32).clamp(
+            NOMINAL_PERIOD - MAX_PERIOD_DEVIATION,
+            NOMINAL_PERIOD + MAX_PERIOD_DEVIATION,
+        );
+        if error.unsigned_abs() <= LOCK_THRESHOLD {
+            self.good_ticks = self.good_ticks.saturating_add(1);
+            if self.good_ticks >= LOCK_COUNT {
+                self.locked = true;
+            }
+        } else {
+            self.good_ticks = 0;
+        }
+        ticks.min(u8::MAX as u32) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic jitter of up to +-3 ms.
+    fn jitter(n: u32) -> i32 {
+        ((n * 7919) % 6001) as i32 - 3000
+    }
+
+    #[test]
+    fn test_lock_with_jitter() {
+        let mut pll = SecondPll::new();
+        assert_eq!(pll.get_next_tick(), None);
+        assert_eq!(pll.handle_tick(5_000_000), 1);
+        for n in 1..30u32 {
+            let t = (5_000_000 + n * 1_000_000).wrapping_add_signed(jitter(n));
+            assert_eq!(pll.handle_tick(t), 1);
+        }
+        assert!(pll.is_locked());
+        assert!(pll.get_phase_error().unsigned_abs() <= 4_000);
+        assert!(pll.get_period().abs_diff(NOMINAL_PERIOD) < 500);
+        let next = pll.get_next_tick().unwrap();
+        assert!(next.abs_diff(35_000_000) < 3_000);
+    }
+    #[test]
+    fn test_drift() {
+        let mut pll = SecondPll::new();
+        let mut t = u32::MAX - 20_000_000; // wraps during the test
+        for _ in 0..200 {
+            pll.handle_tick(t);
+            t = t.wrapping_add(1_000_050);
+        }
+        assert!(pll.is_locked());
+        assert!(pll.get_period().abs_diff(1_000_050) <= 2);
+        assert!(pll.get_phase_error().abs() <= 20);
+    }
+    #[test]
+    fn test_missed_tick() {
+        let mut pll = SecondPll::new();
+        for n in 0..10 {
+            pll.handle_tick(n * 1_000_000);
+        }
+        // no tick at 10 s, like the 59th second of DCF77:
+        assert_eq!(pll.handle_tick(11_001_000), 2);
+        assert!(pll.is_locked());
+        assert_eq!(pll.handle_tick(12_000_000), 1);
+    }
+    #[test]
+    fn test_glitch() {
+        let mut pll = SecondPll::new();
+        for n in 0..10 {
+            pll.handle_tick(n * 1_000_000);
+        }
+        assert_eq!(pll.handle_tick(9_200_000), 0);
+        assert_eq!(pll.handle_tick(9_700_000), 0);
+        assert!(pll.is_locked());
+        assert_eq!(pll.handle_tick(10_000_000), 1);
+        assert_eq!(pll.get_last_tick(), Some(10_000_000));
+    }
+    #[test]
+    fn test_phase_jump() {
+        let mut pll = SecondPll::new();
+        for n in 0..10 {
+            pll.handle_tick(n * 1_000_000);
+        }
+        // new phase, 400 ms later:
+        for n in 10..=12 {
+            assert_eq!(pll.handle_tick(n * 1_000_000 + 400_000), 0);
+            assert!(pll.is_locked());
+        }
+        assert_eq!(pll.handle_tick(13_400_000), 4);
+        assert!(!pll.is_locked());
+        for n in 14..20 {
+            assert_eq!(pll.handle_tick(n * 1_000_000 + 400_000), 1);
+        }
+        assert!(pll.is_locked());
+    }
+}
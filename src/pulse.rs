@@ -0,0 +1,788 @@
+//! Classification of the pulses of amplitude-modulated time codes into bits.
+//!
+//! `PulseDecoder` consumes the edges of the demodulated signal, classifies the width
+//! of each pulse according to a `PulseProfile`, detects the start of a new minute using
+//! a `MinuteDetector`, and writes the bits into a bit buffer indexed by second.
+//!
+//! A pulse starts at the beginning of each second, so the edges passed in are
+//! relative to the pulse and not to the signal level: for DCF77 and WWVB a pulse is a
+//! period of reduced carrier, for JJY it is a period of full carrier.
+//!
+//! MSF transmits an A and a B bit in each second, which `PulseDecoder` writes into two
+//! bit buffers that together make up an `msf::BitBuffer`.
+//!
+//! Receivers may stretch or shorten the pulses, so `PulseDecoder` can optionally learn
+//! the actual pulse widths from a histogram over the last few minutes.
+
+use crate::marker::{MarkerStyle, MinuteDetector};
+use crate::radio_datetime_helpers::time_diff;
+use crate::BIT_BUFFER_SIZE;
+
+/// Width of a bin of the pulse width histogram in microseconds.
+const BIN_WIDTH: u32 = 10_000;
+
+/// Number of bins of the pulse width histogram, covering pulses up to one second.
+const BINS: usize = 100;
+
+/// Minimum number of pulses in the histogram before the pulse widths are learned.
+const MIN_PULSES: u32 = 30;
+
+/// Classification of a single pulse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pulse {
+    /// pulse for a 0 bit
+    Zero,
+    /// pulse for a 1 bit
+    One,
+    /// position or minute marker
+    Marker,
+    /// width of the pulse is not within the tolerance of any symbol
+    Invalid,
+}
+
+/// Nominal pulse widths of a station, in microseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PulseProfile {
+    /// width of a pulse for a 0 bit
+    pub zero_width: u32,
+    /// width of a pulse for a 1 bit
+    pub one_width: u32,
+    /// width of a pulse for an A bit and a B bit of 1, None if the station transmits a
+    /// single bit per second
+    pub b_one_width: Option<u32>,
+    /// width of a marker pulse, None if the station does not use markers
+    pub marker_width: Option<u32>,
+    /// maximum deviation from the nominal widths
+    pub tolerance: u32,
+    /// how the start of a minute is marked
+    pub marker_style: MarkerStyle,
+}
+
+impl PulseProfile {
+    /// DCF77: 100 ms for 0, 200 ms for 1, no pulse in the last second of the minute.
+    pub const DCF77: Self = Self {
+        zero_width: 100_000,
+        one_width: 200_000,
+        b_one_width: None,
+        marker_width: None,
+        tolerance: 40_000,
+        marker_style: MarkerStyle::Gap,
+    };
+    /// WWVB: 200 ms for 0, 500 ms for 1, 800 ms for markers.
+    pub const WWVB: Self = Self {
+        zero_width: 200_000,
+        one_width: 500_000,
+        b_one_width: None,
+        marker_width: Some(800_000),
+        tolerance: 100_000,
+        marker_style: MarkerStyle::DoubleMarker,
+    };
+    /// MSF: 100 ms for A=0 B=0, 200 ms for A=1 B=0, 300 ms for A=1 B=1, 500 ms for the
+    /// minute marker.
+    ///
+    /// A=0 B=1 is a pulse of 100 ms followed by a second one from 200 to 300 ms.
+    pub const MSF: Self = Self {
+        zero_width: 100_000,
+        one_width: 200_000,
+        b_one_width: Some(300_000),
+        marker_width: Some(500_000),
+        tolerance: 40_000,
+        marker_style: MarkerStyle::LongPulse,
+    };
+    /// JJY: 800 ms for 0, 500 ms for 1, 200 ms for markers, of full carrier.
+    pub const JJY: Self = Self {
+        zero_width: 800_000,
+        one_width: 500_000,
+        b_one_width: None,
+        marker_width: Some(200_000),
+        tolerance: 100_000,
+        marker_style: MarkerStyle::DoubleMarker,
+    };
+    /// WWV and WWVH: 170 ms for 0, 470 ms for 1, 770 ms for markers, of 100 Hz tone.
+    pub const WWV: Self = Self {
+        zero_width: 170_000,
+        one_width: 470_000,
+        b_one_width: None,
+        marker_width: Some(770_000),
+        tolerance: 100_000,
+        marker_style: MarkerStyle::MarkerGap,
+    };
+
+    /// Iterate over the nominal widths of the symbols.
+    fn widths(&self) -> impl Iterator<Item = u32> {
+        [
+            Some(self.zero_width),
+            Some(self.one_width),
+            self.b_one_width,
+            self.marker_width,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Classify the given pulse width.
+    ///
+    /// # Arguments
+    /// * `width` - the width of the pulse in microseconds
+    pub fn classify(&self, width: u32) -> Pulse {
+        let near = |nominal: u32| width.abs_diff(nominal) <= self.tolerance;
+        if near(self.zero_width) {
+            Pulse::Zero
+        } else if near(self.one_width) || self.b_one_width.is_some_and(near) {
+            Pulse::One
+        } else if self.marker_width.is_some_and(near) {
+            Pulse::Marker
+        } else {
+            Pulse::Invalid
+        }
+    }
+
+    /// Classify the B bit of the given pulse width, None if the station transmits a
+    /// single bit per second or the pulse is not a 0 or 1 bit.
+    ///
+    /// The B bit of a pulse classified as 0 can still be 1 if a second pulse follows
+    /// within the same second.
+    ///
+    /// # Arguments
+    /// * `width` - the width of the pulse in microseconds
+    pub fn classify_b(&self, width: u32) -> Option<bool> {
+        let b_one_width = self.b_one_width?;
+        match self.classify(width) {
+            Pulse::Zero | Pulse::One => Some(width.abs_diff(b_one_width) <= self.tolerance),
+            Pulse::Marker | Pulse::Invalid => None,
+        }
+    }
+}
+
+/// Turns edge timestamps into the bits of a minute.
+#[derive(Clone, Copy)]
+pub struct PulseDecoder {
+    profile: PulseProfile,
+    bit_buffer: [Option<bool>; BIT_BUFFER_SIZE],
+    minute_buffer: [Option<bool>; BIT_BUFFER_SIZE],
+    bit_buffer_b: [Option<bool>; BIT_BUFFER_SIZE],
+    minute_buffer_b: [Option<bool>; BIT_BUFFER_SIZE],
+    detector: MinuteDetector,
+    new_minute: bool,
+    elapsed: u32,
+    second_start: Option<u32>,
+    pulse_start: Option<u32>,
+    b_pulse_start: Option<u32>,
+    last_pulse: Option<Pulse>,
+    last_width: Option<u32>,
+    adaptive: bool,
+    learned: PulseProfile,
+    histogram: [u16; BINS],
+    quality: u8,
+}
+
+impl PulseDecoder {
+    /// Initialize a new PulseDecoder instance.
+    ///
+    /// # Arguments
+    /// * `profile` - the pulse widths of the station to decode
+    pub fn new(profile: PulseProfile) -> Self {
+        Self {
+            profile,
+            bit_buffer: [None; BIT_BUFFER_SIZE],
+            minute_buffer: [None; BIT_BUFFER_SIZE],
+            bit_buffer_b: [None; BIT_BUFFER_SIZE],
+            minute_buffer_b: [None; BIT_BUFFER_SIZE],
+            detector: MinuteDetector::new(profile.marker_style),
+            new_minute: false,
+            elapsed: 0,
+            second_start: None,
+            pulse_start: None,
+            b_pulse_start: None,
+            last_pulse: None,
+            last_width: None,
+            adaptive: false,
+            learned: profile,
+            histogram: [0; BINS],
+            quality: 0,
+        }
+    }
+
+    /// Get the nominal pulse profile.
+    pub fn get_profile(&self) -> PulseProfile {
+        self.profile
+    }
+
+    /// Enable or disable learning the pulse widths from the received pulses.
+    ///
+    /// When enabled, the pulse widths are learned from a histogram over the last few
+    /// minutes at the start of each minute, and pulses are classified using these
+    /// learned widths. Disabling resets the learned widths to the nominal ones.
+    ///
+    /// # Arguments
+    /// * `adaptive` - if the pulse widths should be learned
+    pub fn set_adaptive(&mut self, adaptive: bool) {
+        self.adaptive = adaptive;
+        if !adaptive {
+            self.learned = self.profile;
+        }
+    }
+
+    /// Returns if the pulse widths are learned from the received pulses.
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// Get the pulse profile used for classification, which contains the learned pulse
+    /// widths if learning is enabled.
+    pub fn get_learned_profile(&self) -> PulseProfile {
+        self.learned
+    }
+
+    /// Get the signal quality, as the percentage of recent pulses within the
+    /// tolerance of the pulse widths used for classification.
+    ///
+    /// This value is updated at the start of each minute.
+    pub fn get_quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Get the current second within the minute.
+    pub fn get_second(&self) -> u8 {
+        self.detector.get_second()
+    }
+
+    /// Get the bits received so far in the current minute, indexed by second.
+    pub fn get_bit_buffer(&self) -> &[Option<bool>; BIT_BUFFER_SIZE] {
+        &self.bit_buffer
+    }
+
+    /// Get the bits of the last completed minute, indexed by second.
+    ///
+    /// Markers and seconds without a valid pulse are None. The first minute after
+    /// startup is incomplete.
+    pub fn get_minute_buffer(&self) -> &[Option<bool>; BIT_BUFFER_SIZE] {
+        &self.minute_buffer
+    }
+
+    /// Get the B bits received so far in the current minute, indexed by second.
+    ///
+    /// All B bits are None unless the profile has a `b_one_width`.
+    pub fn get_bit_buffer_b(&self) -> &[Option<bool>; BIT_BUFFER_SIZE] {
+        &self.bit_buffer_b
+    }
+
+    /// Get the B bits of the last completed minute, indexed by second.
+    ///
+    /// Together with `get_minute_buffer()` for the A bits, this is the input for
+    /// `msf::decode_time()`.
+    pub fn get_minute_buffer_b(&self) -> &[Option<bool>; BIT_BUFFER_SIZE] {
+        &self.minute_buffer_b
+    }
+
+    /// Get the length in seconds of the last completed minute.
+    pub fn get_minute_length(&self) -> u8 {
+        self.detector.get_minute_length()
+    }
+
+    /// Returns if the last edge completed a minute, which is then available through
+    /// `get_minute_buffer()` and `get_minute_length()`.
+    ///
+    /// A new minute is detected at the end of a pulse, see
+    /// `MinuteDetector::is_new_minute()` for stations with double markers.
+    pub fn is_new_minute(&self) -> bool {
+        self.new_minute
+    }
+
+    /// Get the classification of the last pulse, if any.
+    pub fn get_last_pulse(&self) -> Option<Pulse> {
+        self.last_pulse
+    }
+
+    /// Get the width of the last pulse in microseconds, if any.
+    pub fn get_last_width(&self) -> Option<u32> {
+        self.last_width
+    }
+
+    /// Handle an edge of the demodulated signal.
+    ///
+    /// # Arguments
+    /// * `timestamp` - the time of the edge in microseconds
+    /// * `active` - if a pulse starts (true) or ends (false) at this edge
+    pub fn handle_edge(&mut self, timestamp: u32, active: bool) {
+        self.new_minute = false;
+        if active {
+            self.handle_pulse_start(timestamp);
+        } else if let Some(start) = self.pulse_start.take() {
+            self.handle_pulse_end(time_diff(start, timestamp));
+        } else if let Some(start) = self.b_pulse_start.take() {
+            self.handle_b_pulse_end(time_diff(start, timestamp));
+        }
+    }
+
+    fn handle_pulse_start(&mut self, timestamp: u32) {
+        self.elapsed = 0;
+        if let Some(second_start) = self.second_start {
+            let offset = time_diff(second_start, timestamp);
+            // Number of seconds since the previous pulse, rounded:
+            self.elapsed = (offset + 500_000) / 1_000_000;
+            if self.elapsed == 0 {
+                // the second pulse of A=0 B=1 starts where a 1 bit would end,
+                // anything else is a glitch within the current second:
+                if self.learned.b_one_width.is_some() && self.learned.classify(offset) == Pulse::One
+                {
+                    self.b_pulse_start = Some(timestamp);
+                }
+                return;
+            }
+        }
+        self.second_start = Some(timestamp);
+        self.pulse_start = Some(timestamp);
+        self.b_pulse_start = None;
+    }
+
+    fn handle_pulse_end(&mut self, width: u32) {
+        let pulse = self.learned.classify(width);
+        if let Some(bin) = self.histogram.get_mut((width / BIN_WIDTH) as usize) {
+            *bin = bin.saturating_add(1);
+        }
+        if !self.detector.handle_pulse(self.elapsed, pulse) {
+            // missed the minute marker, the buffer contents are meaningless now
+            self.bit_buffer = [None; BIT_BUFFER_SIZE];
+            self.bit_buffer_b = [None; BIT_BUFFER_SIZE];
+        }
+        let pulse = if self.detector.is_minute_start() {
+            self.start_minute();
+            // with the pulse widths just learned:
+            self.learned.classify(width)
+        } else {
+            pulse
+        };
+        self.new_minute = self.detector.is_new_minute();
+        let second = self.detector.get_second() as usize;
+        self.bit_buffer[second] = match pulse {
+            Pulse::Zero => Some(false),
+            Pulse::One => Some(true),
+            Pulse::Marker | Pulse::Invalid => None,
+        };
+        self.bit_buffer_b[second] = self.learned.classify_b(width);
+        self.last_pulse = Some(pulse);
+        self.last_width = Some(width);
+    }
+
+    /// Set the B bit of A=0 B=1 if the second pulse within the current second has the
+    /// width of a 0 bit.
+    fn handle_b_pulse_end(&mut self, width: u32) {
+        if self.last_pulse == Some(Pulse::Zero) && self.learned.classify(width) == Pulse::Zero {
+            self.bit_buffer_b[self.detector.get_second() as usize] = Some(true);
+        }
+    }
+
+    /// Complete the current minute and start a new one.
+    fn start_minute(&mut self) {
+        let minute_length = self.detector.get_minute_length() as usize;
+        self.minute_buffer = self.bit_buffer;
+        self.minute_buffer_b = self.bit_buffer_b;
+        for bit in &mut self.minute_buffer[minute_length..] {
+            *bit = None;
+        }
+        for bit in &mut self.minute_buffer_b[minute_length..] {
+            *bit = None;
+        }
+        self.bit_buffer = [None; BIT_BUFFER_SIZE];
+        self.bit_buffer_b = [None; BIT_BUFFER_SIZE];
+        self.update_statistics();
+    }
+
+    /// Learn the pulse widths, update the signal quality, and let older pulses fade
+    /// out of the histogram.
+    fn update_statistics(&mut self) {
+        let total = self.histogram.iter().map(|c| *c as u32).sum::<u32>();
+        if self.adaptive && total >= MIN_PULSES {
+            // Find the common offset (e.g. due to AGC) which covers most pulses:
+            let tolerance = self.profile.tolerance as i32;
+            let mut best = (0i32, 0);
+            for step in -2 * tolerance / BIN_WIDTH as i32..=2 * tolerance / BIN_WIDTH as i32 {
+                let offset = step * BIN_WIDTH as i32;
+                let count = self
+                    .profile
+                    .widths()
+                    .map(|w| self.get_near(w as i32 + offset).0)
+                    .sum::<u32>();
+                if count > best.1 || (count == best.1 && offset.abs() < best.0.abs()) {
+                    best = (offset, count);
+                }
+            }
+            // and refine each width by the mean of the pulses near it:
+            let learn = |w: u32| {
+                let center = w as i32 + best.0;
+                match self.get_near(center) {
+                    (0, _) => center.max(0) as u32,
+                    (count, sum) => (sum / count as u64) as u32,
+                }
+            };
+            self.learned = PulseProfile {
+                zero_width: learn(self.profile.zero_width),
+                one_width: learn(self.profile.one_width),
+                b_one_width: self.profile.b_one_width.map(learn),
+                marker_width: self.profile.marker_width.map(learn),
+                ..self.profile
+            };
+        }
+        let near = self
+            .learned
+            .widths()
+            .map(|w| self.get_near(w as i32).0)
+            .sum::<u32>();
+        if let Some(quality) = (near.min(total) * 100).checked_div(total) {
+            self.quality = quality as u8;
+        }
+        for bin in &mut self.histogram {
+            *bin /= 2;
+        }
+    }
+
+    /// Returns the number of pulses in the histogram within the tolerance of the given
+    /// width, and the sum of their widths.
+    ///
+    /// # Arguments
+    /// * `width` - the width to look around in microseconds
+    fn get_near(&self, width: i32) -> (u32, u64) {
+        let mut result = (0, 0);
+        for (idx, count) in self.histogram.iter().enumerate() {
+            let center = (idx as u32 * BIN_WIDTH + BIN_WIDTH / 2) as i32;
+            if *count > 0 && center.abs_diff(width) <= self.profile.tolerance {
+                result.0 += *count as u32;
+                result.1 += *count as u64 * center as u64;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RadioDateTimeUtils, DST_SUMMER};
+
+    /// Feed one pulse starting at `second` seconds after `t0`.
+    fn pulse(decoder: &mut PulseDecoder, t0: u32, second: u32, width: u32) {
+        let start = t0.wrapping_add(second * 1_000_000);
+        decoder.handle_edge(start, true);
+        decoder.handle_edge(start.wrapping_add(width), false);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(PulseProfile::DCF77.classify(100_000), Pulse::Zero);
+        assert_eq!(PulseProfile::DCF77.classify(235_000), Pulse::One);
+        assert_eq!(PulseProfile::DCF77.classify(150_000), Pulse::Invalid);
+        assert_eq!(PulseProfile::DCF77.classify(800_000), Pulse::Invalid);
+        assert_eq!(PulseProfile::WWVB.classify(780_000), Pulse::Marker);
+        assert_eq!(PulseProfile::JJY.classify(780_000), Pulse::Zero);
+        assert_eq!(PulseProfile::JJY.classify(210_000), Pulse::Marker);
+        assert_eq!(PulseProfile::MSF.classify(210_000), Pulse::One);
+        assert_eq!(PulseProfile::MSF.classify(290_000), Pulse::One);
+        assert_eq!(PulseProfile::MSF.classify(520_000), Pulse::Marker);
+        assert_eq!(PulseProfile::MSF.classify_b(110_000), Some(false));
+        assert_eq!(PulseProfile::MSF.classify_b(210_000), Some(false));
+        assert_eq!(PulseProfile::MSF.classify_b(290_000), Some(true));
+        assert_eq!(PulseProfile::MSF.classify_b(500_000), None);
+        assert_eq!(PulseProfile::MSF.classify_b(250_000), None);
+        assert_eq!(PulseProfile::DCF77.classify_b(200_000), None);
+    }
+    #[test]
+    fn test_dcf77_minute() {
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        let t0 = u32::MAX - 10_000_000; // wraps during the minute
+        for second in 0..=58 {
+            let width = if second == 20 || second == 33 {
+                200_000
+            } else {
+                100_000
+            };
+            pulse(&mut decoder, t0, second, width);
+            assert!(!decoder.is_new_minute());
+        }
+        assert_eq!(decoder.get_second(), 58);
+        decoder.handle_edge(t0.wrapping_add(60_000_000), true);
+        assert!(!decoder.is_new_minute());
+        decoder.handle_edge(t0.wrapping_add(60_195_000), false);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 0);
+        assert_eq!(decoder.get_minute_length(), 60);
+        let minute = decoder.get_minute_buffer();
+        assert_eq!(minute[0], Some(false));
+        assert_eq!(minute[20], Some(true));
+        assert_eq!(minute[33], Some(true));
+        assert_eq!(minute[58], Some(false));
+        assert_eq!(minute[59], None);
+        assert_eq!(decoder.get_bit_buffer()[0], Some(true));
+        assert_eq!(decoder.get_last_pulse(), Some(Pulse::One));
+        assert_eq!(decoder.get_last_width(), Some(195_000));
+    }
+    #[test]
+    fn test_dcf77_leap_second() {
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        for second in 0..=59 {
+            pulse(&mut decoder, 0, second, 100_000);
+        }
+        pulse(&mut decoder, 0, 61, 100_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_minute_length(), 61);
+        assert_eq!(decoder.get_minute_buffer()[59], Some(false));
+    }
+    /// Feed a DCF77 minute with the given extra pulse width, 1 bits every third second.
+    fn dcf77_minute(decoder: &mut PulseDecoder, offset: u32, stretch: u32) {
+        for second in 0..=58 {
+            let width = if second % 3 == 0 { 200_000 } else { 100_000 };
+            pulse(decoder, 0, offset + second, width + stretch);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_stretched_pulses() {
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        decoder.set_adaptive(true);
+        assert!(decoder.is_adaptive());
+        dcf77_minute(&mut decoder, 0, 55_000);
+        // too far off the nominal widths:
+        assert_eq!(decoder.get_last_pulse(), Some(Pulse::Invalid));
+        dcf77_minute(&mut decoder, 60, 55_000);
+        assert_eq!(decoder.get_minute_length(), 60);
+        assert_eq!(decoder.get_quality(), 100);
+        let learned = decoder.get_learned_profile();
+        assert_eq!(learned.zero_width, 155_000);
+        assert_eq!(learned.one_width, 255_000);
+        assert_eq!(learned.marker_width, None);
+        dcf77_minute(&mut decoder, 120, 55_000);
+        let minute = decoder.get_minute_buffer();
+        assert_eq!(
+            minute[0..=3],
+            [Some(true), Some(false), Some(false), Some(true)]
+        );
+        assert_eq!(minute[58], Some(false));
+        decoder.set_adaptive(false);
+        assert_eq!(decoder.get_learned_profile(), PulseProfile::DCF77);
+    }
+    #[test]
+    fn test_quality_fixed_widths() {
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        dcf77_minute(&mut decoder, 0, 55_000);
+        dcf77_minute(&mut decoder, 60, 55_000);
+        assert_eq!(decoder.get_quality(), 0);
+        assert_eq!(decoder.get_learned_profile(), PulseProfile::DCF77);
+        assert_eq!(decoder.get_minute_buffer()[0], None);
+        dcf77_minute(&mut decoder, 120, 10_000);
+        pulse(&mut decoder, 0, 180, 210_000);
+        assert!(decoder.is_new_minute());
+        // the older stretched pulses are fading out of the histogram:
+        assert_eq!(decoder.get_quality(), 57);
+        assert_eq!(decoder.get_minute_buffer()[0], Some(true));
+    }
+    #[test]
+    fn test_missed_pulse_and_glitch() {
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        pulse(&mut decoder, 0, 0, 100_000);
+        pulse(&mut decoder, 0, 1, 200_000);
+        // glitch, ignored:
+        decoder.handle_edge(1_400_000, true);
+        decoder.handle_edge(1_410_000, false);
+        // three seconds later:
+        pulse(&mut decoder, 0, 4, 100_000);
+        assert!(!decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 4);
+        let bits = decoder.get_bit_buffer();
+        assert_eq!(
+            bits[0..=4],
+            [Some(false), Some(true), None, None, Some(false)]
+        );
+    }
+    #[test]
+    fn test_overflow_clears_buffer() {
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        for second in 0..=60 {
+            pulse(&mut decoder, 0, second, 100_000);
+        }
+        pulse(&mut decoder, 0, 61, 200_000);
+        assert!(!decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 0);
+        assert_eq!(decoder.get_bit_buffer()[0], Some(true));
+        assert_eq!(decoder.get_bit_buffer()[1], None);
+    }
+
+    #[test]
+    fn test_dcf77_decode_time() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_year(Some(24), true, false);
+        rdt.set_month(Some(6), true, false);
+        rdt.set_weekday(Some(7), true, false);
+        rdt.set_day(Some(16), true, false);
+        rdt.set_hour(Some(12), true, false);
+        rdt.set_minute(Some(34), true, false);
+        rdt.set_dst(Some(true), Some(false), false);
+        rdt.set_leap_second(Some(false), 60);
+        let (bits, length) = crate::dcf77::encode_time(&rdt).unwrap();
+        let mut decoder = PulseDecoder::new(PulseProfile::DCF77);
+        // the end of the previous minute:
+        pulse(&mut decoder, 0, 0, 100_000);
+        for second in 0..length as u32 - 1 {
+            let width = if bits[second as usize] == Some(true) {
+                200_000
+            } else {
+                100_000
+            };
+            pulse(&mut decoder, 0, second + 2, width);
+        }
+        pulse(&mut decoder, 0, length as u32 + 2, 100_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_minute_length(), 60);
+        let mut decoded = RadioDateTimeUtils::new(7);
+        crate::dcf77::decode_time(
+            &mut decoded,
+            decoder.get_minute_buffer(),
+            decoder.get_minute_length(),
+            false,
+        );
+        assert_eq!(decoded.get_minute(), Some(34));
+        assert_eq!(decoded.get_hour(), Some(12));
+        assert_eq!(decoded.get_leap_second(), Some(0));
+    }
+    #[test]
+    fn test_msf_minute() {
+        let mut decoder = PulseDecoder::new(PulseProfile::MSF);
+        pulse(&mut decoder, 0, 0, 500_000);
+        for second in 1..60 {
+            // A=1 B=1, A=0 B=1, A=1 B=0, and A=0 B=0:
+            match second % 4 {
+                0 => pulse(&mut decoder, 0, second, 300_000),
+                1 => {
+                    pulse(&mut decoder, 0, second, 100_000);
+                    decoder.handle_edge(second * 1_000_000 + 200_000, true);
+                    decoder.handle_edge(second * 1_000_000 + 300_000, false);
+                }
+                2 => pulse(&mut decoder, 0, second, 200_000),
+                _ => pulse(&mut decoder, 0, second, 100_000),
+            }
+        }
+        pulse(&mut decoder, 0, 60, 500_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_minute_length(), 60);
+        let minute = decoder.get_minute_buffer();
+        assert_eq!(minute[0], None);
+        assert_eq!(
+            minute[1..=4],
+            [Some(false), Some(true), Some(false), Some(true)]
+        );
+        assert_eq!(minute[59], Some(false));
+        let minute_b = decoder.get_minute_buffer_b();
+        assert_eq!(minute_b[0], None);
+        assert_eq!(
+            minute_b[1..=4],
+            [Some(true), Some(false), Some(false), Some(true)]
+        );
+        assert_eq!(minute_b[59], Some(false));
+        assert_eq!(decoder.get_bit_buffer_b()[0], None);
+    }
+    #[test]
+    fn test_msf_decode_time() {
+        let rdt = crate::tests::rdt_at(0, (24, 6, 16), (12, 34), true);
+        let (bits, length) = crate::msf::encode_time(&rdt).unwrap();
+        let mut decoder = PulseDecoder::new(PulseProfile::MSF);
+        pulse(&mut decoder, 0, 0, 500_000);
+        for second in 1..length as u32 {
+            let t0 = second * 1_000_000;
+            match (bits.a[second as usize], bits.b[second as usize]) {
+                (Some(false), Some(true)) => {
+                    pulse(&mut decoder, 0, second, 100_000);
+                    decoder.handle_edge(t0 + 200_000, true);
+                    decoder.handle_edge(t0 + 300_000, false);
+                }
+                (Some(true), Some(true)) => pulse(&mut decoder, 0, second, 300_000),
+                (Some(true), _) => pulse(&mut decoder, 0, second, 200_000),
+                _ => pulse(&mut decoder, 0, second, 100_000),
+            }
+        }
+        pulse(&mut decoder, 0, length as u32, 500_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_minute_length(), 60);
+        assert_eq!(decoder.get_minute_buffer()[1..], bits.a[1..]);
+        assert_eq!(decoder.get_minute_buffer_b()[1..], bits.b[1..]);
+        let bit_buffer = crate::msf::BitBuffer {
+            a: *decoder.get_minute_buffer(),
+            b: *decoder.get_minute_buffer_b(),
+        };
+        let mut decoded = RadioDateTimeUtils::new(0);
+        crate::msf::decode_time(&mut decoded, &bit_buffer, 60, false);
+        assert_eq!(decoded.get_year(), Some(24));
+        assert_eq!(decoded.get_month(), Some(6));
+        assert_eq!(decoded.get_day(), Some(16));
+        assert_eq!(decoded.get_hour(), Some(12));
+        assert_eq!(decoded.get_minute(), Some(34));
+        assert_eq!(decoded.get_dst(), Some(DST_SUMMER));
+    }
+
+    /// Feed a WWVB minute of zeros starting at second `offset` after 0.
+    fn wwvb_minute(decoder: &mut PulseDecoder, offset: u32, length: u32) {
+        for second in 0..length {
+            let width = if crate::wwvb::MARKERS.contains(&(second as usize)) || second == 60 {
+                800_000
+            } else {
+                200_000
+            };
+            pulse(decoder, 0, offset + second, width);
+        }
+    }
+
+    #[test]
+    fn test_wwvb_minute() {
+        let mut decoder = PulseDecoder::new(PulseProfile::WWVB);
+        wwvb_minute(&mut decoder, 0, 60);
+        wwvb_minute(&mut decoder, 60, 60);
+        // partial first minute, the marker at second 0 is not preceded by another one
+        pulse(&mut decoder, 0, 120, 800_000);
+        assert!(!decoder.is_new_minute());
+        pulse(&mut decoder, 0, 121, 500_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 1);
+        assert_eq!(decoder.get_minute_length(), 60);
+        let minute = decoder.get_minute_buffer();
+        assert_eq!(minute[0], None);
+        assert_eq!(minute[1], Some(false));
+        assert_eq!(minute[59], None);
+        assert_eq!(decoder.get_bit_buffer()[1], Some(true));
+    }
+    #[test]
+    fn test_wwvb_leap_second() {
+        let mut decoder = PulseDecoder::new(PulseProfile::WWVB);
+        wwvb_minute(&mut decoder, 0, 60);
+        // markers at seconds 59, 60, and 0 of the next minute:
+        wwvb_minute(&mut decoder, 60, 61);
+        pulse(&mut decoder, 0, 121, 800_000);
+        assert!(!decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 0);
+        pulse(&mut decoder, 0, 122, 200_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 1);
+        assert_eq!(decoder.get_minute_length(), 61);
+        assert_eq!(decoder.get_minute_buffer()[58], Some(false));
+        assert_eq!(decoder.get_minute_buffer()[60], None);
+    }
+    #[test]
+    fn test_wwv_minute() {
+        let rdt = crate::tests::rdt_at(0, (24, 6, 16), (12, 34), false);
+        let (widths, length) = crate::wwv::encode_time(&rdt).unwrap();
+        let mut decoder = PulseDecoder::new(PulseProfile::WWV);
+        // the marker at the end of the previous minute, followed by no pulse at second 0:
+        pulse(&mut decoder, 0, 0, 770_000);
+        for (second, width) in widths.iter().enumerate() {
+            if let Some(width) = width {
+                pulse(&mut decoder, 0, second as u32 + 1, *width);
+            }
+        }
+        assert_eq!(decoder.get_second(), 59);
+        pulse(&mut decoder, 0, length as u32 + 2, 170_000);
+        assert!(decoder.is_new_minute());
+        assert_eq!(decoder.get_second(), 1);
+        assert_eq!(decoder.get_minute_length(), 60);
+        assert_eq!(
+            decoder.get_minute_buffer(),
+            &crate::wwv::get_bit_buffer(&widths).0
+        );
+    }
+}
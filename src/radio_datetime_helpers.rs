@@ -1,3 +1,5 @@
+use crate::DecodeError;
+
 /// Return the difference in microseconds between two timestamps.
 ///
 /// This function takes wrapping of the parameters into account,
@@ -16,6 +18,87 @@ pub fn time_diff(t0: u32, t1: u32) -> u32 {
     }
 }
 
+/// Unsigned timer value which wraps around at its maximum, as used by `time_diff_ticks()`.
+pub trait Timestamp: Copy {
+    /// Return the number of ticks from `earlier` to `self`, taking one wrap into account.
+    fn wrapping_diff(self, earlier: Self) -> Self;
+
+    /// Convert the value to u64 without loss.
+    fn to_u64(self) -> u64;
+}
+
+macro_rules! impl_timestamp {
+    ($($t:ty),*) => {
+        $(
+            impl Timestamp for $t {
+                fn wrapping_diff(self, earlier: Self) -> Self {
+                    self.wrapping_sub(earlier)
+                }
+
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+            }
+        )*
+    };
+}
+
+impl_timestamp!(u16, u32, u64);
+
+/// Unit of the ticks of a timer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// ticks of 1 ms
+    Millisecond,
+    /// ticks of 1 µs, like `time_diff()`
+    Microsecond,
+    /// ticks of 1 ns
+    Nanosecond,
+}
+
+/// Return the difference in ticks between two timestamps of any timer width.
+///
+/// Like `time_diff()`, this takes wrapping of the timer into account, so `t1` is
+/// assumed to be less than one full timer period after `t0`.
+///
+/// # Arguments
+/// * `t0` - old timestamp
+/// * `t1` - new timestamp
+pub fn time_diff_ticks<T: Timestamp>(t0: T, t1: T) -> T {
+    t1.wrapping_diff(t0)
+}
+
+/// Return the difference in microseconds between two timestamps of any timer width
+/// and unit. Nanoseconds are truncated to whole microseconds.
+///
+/// # Arguments
+/// * `t0` - old timestamp
+/// * `t1` - new timestamp
+/// * `unit` - the unit of the timestamps
+pub fn time_diff_micros<T: Timestamp>(t0: T, t1: T, unit: TimeUnit) -> u64 {
+    let ticks = time_diff_ticks(t0, t1).to_u64();
+    match unit {
+        TimeUnit::Millisecond => ticks.saturating_mul(1000),
+        TimeUnit::Microsecond => ticks,
+        TimeUnit::Nanosecond => ticks / 1000,
+    }
+}
+
+/// Return the difference in microseconds between two timestamps like
+/// `time_diff_micros()`, or None if the difference is larger than `max_gap`.
+///
+/// A large gap means that events were missed, or that the timer wrapped more than once
+/// in between for narrow timers, so the difference cannot be trusted.
+///
+/// # Arguments
+/// * `t0` - old timestamp
+/// * `t1` - new timestamp
+/// * `unit` - the unit of the timestamps
+/// * `max_gap` - the largest plausible difference in microseconds
+pub fn time_diff_checked<T: Timestamp>(t0: T, t1: T, unit: TimeUnit, max_gap: u64) -> Option<u64> {
+    Some(time_diff_micros(t0, t1, unit)).filter(|diff| *diff <= max_gap)
+}
+
 /// Returns the BCD-encoded value of the given buffer over the given range, or None if the input is invalid.
 ///
 /// # Arguments
@@ -23,10 +106,25 @@ pub fn time_diff(t0: u32, t1: u32) -> u32 {
 /// * `start` - start bit position (least significant)
 /// * `stop` - stop bit position (most significant)
 pub fn get_bcd_value(bit_buffer: &[Option<bool>], start: usize, stop: usize) -> Option<u8> {
+    try_get_bcd_value(bit_buffer, start, stop).ok()
+}
+
+/// Returns the BCD-encoded value of the given buffer over the given range like
+/// `get_bcd_value()`, or the reason why the input is invalid.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits
+/// * `start` - start bit position (least significant)
+/// * `stop` - stop bit position (most significant)
+pub fn try_get_bcd_value(
+    bit_buffer: &[Option<bool>],
+    start: usize,
+    stop: usize,
+) -> Result<u8, DecodeError> {
     const MAX_RANGE: usize = 8;
     let (p0, p1) = min_max(start, stop);
     if p1 - p0 >= MAX_RANGE {
-        return None;
+        return Err(DecodeError::RangeTooWide);
     }
     let mut bcd = 0;
     let mut mult = 1;
@@ -36,24 +134,184 @@ pub fn get_bcd_value(bit_buffer: &[Option<bool>], start: usize, stop: usize) ->
     let step: isize = if start < stop { 1 } else { -1 };
     // The test value for idx is usize::MAX if stop is 0, but we stop just in time.
     while idx != (stop as isize + step) as usize {
-        let bit = bit_buffer[idx]?;
+        let bit = bit_buffer[idx].ok_or(DecodeError::MissingBit { index: idx })?;
         bcd += mult * bit as u8;
         mult *= 2;
         if mult == 16 {
             if bcd > 9 {
-                return None;
+                return Err(DecodeError::BadNibble);
             }
             mult = 10;
         }
         idx = (idx as isize + step) as usize;
     }
     if bcd < 100 {
-        Some(bcd)
+        Ok(bcd)
     } else {
-        None
+        Err(DecodeError::OutOfRange)
     }
 }
 
+/// Write the BCD-encoded value into the given buffer over the given range, return if the
+/// operation succeeded. This is the inverse of `get_bcd_value()`.
+///
+/// The buffer is left untouched if the range is too wide or the value does not fit it.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer to write the bits to
+/// * `start` - start bit position (least significant)
+/// * `stop` - stop bit position (most significant)
+/// * `value` - the value to write
+pub fn set_bcd_value(
+    bit_buffer: &mut [Option<bool>],
+    start: usize,
+    stop: usize,
+    value: u8,
+) -> bool {
+    try_set_bcd_value(bit_buffer, start, stop, value).is_ok()
+}
+
+/// Write the BCD-encoded value into the given buffer over the given range like
+/// `set_bcd_value()`, or return why it could not be written.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer to write the bits to
+/// * `start` - start bit position (least significant)
+/// * `stop` - stop bit position (most significant)
+/// * `value` - the value to write
+pub fn try_set_bcd_value(
+    bit_buffer: &mut [Option<bool>],
+    start: usize,
+    stop: usize,
+    value: u8,
+) -> Result<(), DecodeError> {
+    const MAX_RANGE: usize = 8;
+    let (p0, p1) = min_max(start, stop);
+    if p1 - p0 >= MAX_RANGE {
+        return Err(DecodeError::RangeTooWide);
+    }
+    let bcd = (value / 10) << 4 | (value % 10);
+    if value >= 100 || (bcd as u16) >> (p1 - p0 + 1) != 0 {
+        return Err(DecodeError::OutOfRange);
+    }
+    // Same manual loop as in get_bcd_value() to support both directions:
+    let mut idx = start;
+    let step: isize = if start < stop { 1 } else { -1 };
+    for i in 0..=p1 - p0 {
+        bit_buffer[idx] = Some((bcd >> i) & 1 == 1);
+        idx = (idx as isize + step) as usize;
+    }
+    Ok(())
+}
+
+/// Returns the BCD-encoded value of up to four digits spread over the given groups of bits,
+/// or None if the input is invalid.
+///
+/// The bits of all groups are taken together as one range, so the groups can skip marker
+/// or unused bits. Each complete digit (nibble) must not exceed 9.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits
+/// * `groups` - (start, stop) positions of each group of bits, least significant group
+///   first. Within a group, `start` is the least significant bit.
+pub fn get_bcd_value_u16(bit_buffer: &[Option<bool>], groups: &[(usize, usize)]) -> Option<u16> {
+    try_get_bcd_value_u16(bit_buffer, groups).ok()
+}
+
+/// Returns the BCD-encoded value of up to four digits spread over the given groups of bits
+/// like `get_bcd_value_u16()`, or the reason why the input is invalid.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits
+/// * `groups` - (start, stop) positions of each group of bits, least significant group
+///   first. Within a group, `start` is the least significant bit.
+pub fn try_get_bcd_value_u16(
+    bit_buffer: &[Option<bool>],
+    groups: &[(usize, usize)],
+) -> Result<u16, DecodeError> {
+    const MAX_RANGE: usize = 16;
+    if get_width(groups) > MAX_RANGE {
+        return Err(DecodeError::RangeTooWide);
+    }
+    let mut value = 0;
+    let mut digit = 0;
+    let mut digit_mult = 1;
+    let mut bit_count = 0;
+    for (start, stop) in groups {
+        let mut idx = *start;
+        let step: isize = if start < stop { 1 } else { -1 };
+        while idx != (*stop as isize + step) as usize {
+            let bit = bit_buffer[idx].ok_or(DecodeError::MissingBit { index: idx })?;
+            digit += (bit as u16) << (bit_count % 4);
+            bit_count += 1;
+            if bit_count % 4 == 0 {
+                if digit > 9 {
+                    return Err(DecodeError::BadNibble);
+                }
+                value += digit_mult * digit;
+                digit = 0;
+                digit_mult = digit_mult.saturating_mul(10);
+            }
+            idx = (idx as isize + step) as usize;
+        }
+    }
+    Ok(value + digit_mult * digit)
+}
+
+/// Write the BCD-encoded value of up to four digits spread over the given groups of bits,
+/// return if the operation succeeded. This is the inverse of `get_bcd_value_u16()`.
+///
+/// The buffer is left untouched if the groups are too wide or the value does not fit them.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer to write the bits to
+/// * `groups` - (start, stop) positions of each group of bits, least significant group
+///   first. Within a group, `start` is the least significant bit.
+/// * `value` - the value to write
+pub fn set_bcd_value_u16(
+    bit_buffer: &mut [Option<bool>],
+    groups: &[(usize, usize)],
+    value: u16,
+) -> bool {
+    try_set_bcd_value_u16(bit_buffer, groups, value).is_ok()
+}
+
+/// Write the BCD-encoded value of up to four digits spread over the given groups of bits
+/// like `set_bcd_value_u16()`, or return why it could not be written.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer to write the bits to
+/// * `groups` - (start, stop) positions of each group of bits, least significant group
+///   first. Within a group, `start` is the least significant bit.
+/// * `value` - the value to write
+pub fn try_set_bcd_value_u16(
+    bit_buffer: &mut [Option<bool>],
+    groups: &[(usize, usize)],
+    value: u16,
+) -> Result<(), DecodeError> {
+    const MAX_RANGE: usize = 16;
+    let width = get_width(groups);
+    if width > MAX_RANGE {
+        return Err(DecodeError::RangeTooWide);
+    }
+    let bcd =
+        (value / 1000) << 12 | (value / 100 % 10) << 8 | (value / 10 % 10) << 4 | (value % 10);
+    if value >= 10_000 || (bcd as u32) >> width != 0 {
+        return Err(DecodeError::OutOfRange);
+    }
+    let mut bit_count = 0;
+    for (start, stop) in groups {
+        let mut idx = *start;
+        let step: isize = if start < stop { 1 } else { -1 };
+        while idx != (*stop as isize + step) as usize {
+            bit_buffer[idx] = Some((bcd >> bit_count) & 1 == 1);
+            bit_count += 1;
+            idx = (idx as isize + step) as usize;
+        }
+    }
+    Ok(())
+}
+
 /// Returns parity of the given buffer over the given range, or None if the input is invalid.
 /// Should be Some(false) for even parity and Some(true) for odd parity.
 ///
@@ -61,21 +319,351 @@ pub fn get_bcd_value(bit_buffer: &[Option<bool>], start: usize, stop: usize) ->
 /// * `bit_buffer` - buffer containing the bits to check.
 /// * `start` - start bit position
 /// * `stop` - stop bit position
-/// `parity` - parity bit value
+/// * `parity` - parity bit value
 pub fn get_parity(
     bit_buffer: &[Option<bool>],
     start: usize,
     stop: usize,
     parity: Option<bool>,
 ) -> Option<bool> {
-    parity?;
-    let mut s_parity = parity.unwrap();
+    try_get_parity(bit_buffer, start, stop, parity?).ok()
+}
+
+/// Returns parity of the given buffer over the given range like `get_parity()`, or the
+/// reason why the input is invalid. Should be Ok(false) for even parity and Ok(true) for
+/// odd parity. The range must lie within `bit_buffer`, an OutOfRange error otherwise.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits to check.
+/// * `start` - start bit position
+/// * `stop` - stop bit position
+/// * `parity` - parity bit value, see `check_parity()` to verify it directly
+pub fn try_get_parity(
+    bit_buffer: &[Option<bool>],
+    start: usize,
+    stop: usize,
+    parity: bool,
+) -> Result<bool, DecodeError> {
+    let mut s_parity = parity;
+    let (p0, p1) = min_max(start, stop);
+    if p1 >= bit_buffer.len() {
+        return Err(DecodeError::OutOfRange);
+    }
+    for (index, bit) in bit_buffer.iter().enumerate().take(p1 + 1).skip(p0) {
+        s_parity ^= bit.ok_or(DecodeError::MissingBit { index })?;
+    }
+    Ok(s_parity)
+}
+
+/// Check the parity of the given buffer over the given range against a parity bit.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits to check.
+/// * `start` - start bit position
+/// * `stop` - stop bit position
+/// * `parity` - position of the parity bit in `bit_buffer`
+/// * `odd` - check for odd parity instead of even parity
+pub fn check_parity(
+    bit_buffer: &[Option<bool>],
+    start: usize,
+    stop: usize,
+    parity: usize,
+    odd: bool,
+) -> Result<(), DecodeError> {
+    let parity_bit = bit_buffer[parity].ok_or(DecodeError::MissingBit { index: parity })?;
+    if try_get_parity(bit_buffer, start, stop, parity_bit)? == odd {
+        Ok(())
+    } else {
+        Err(DecodeError::ParityMismatch)
+    }
+}
+
+/// Write the parity bit over the given range, return if the operation succeeded.
+/// Afterwards, `get_parity()` over the same range and the written parity bit returns
+/// `odd`.
+///
+/// The parity bit is left untouched if any bit in the range is None, or if the parity
+/// bit is inside the range.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer to write the parity bit to
+/// * `start` - start bit position
+/// * `stop` - stop bit position
+/// * `parity` - position of the parity bit in `bit_buffer`
+/// * `odd` - write odd parity instead of even parity
+pub fn set_parity(
+    bit_buffer: &mut [Option<bool>],
+    start: usize,
+    stop: usize,
+    parity: usize,
+    odd: bool,
+) -> bool {
+    try_set_parity(bit_buffer, start, stop, parity, odd).is_ok()
+}
+
+/// Write the parity bit over the given range like `set_parity()`, or return why it could
+/// not be written.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer to write the parity bit to
+/// * `start` - start bit position
+/// * `stop` - stop bit position
+/// * `parity` - position of the parity bit in `bit_buffer`
+/// * `odd` - write odd parity instead of even parity
+pub fn try_set_parity(
+    bit_buffer: &mut [Option<bool>],
+    start: usize,
+    stop: usize,
+    parity: usize,
+    odd: bool,
+) -> Result<(), DecodeError> {
     let (p0, p1) = min_max(start, stop);
-    for bit in &bit_buffer[p0..=p1] {
-        (*bit)?;
-        s_parity ^= bit.unwrap();
+    if (p0..=p1).contains(&parity) || parity >= bit_buffer.len() {
+        return Err(DecodeError::OutOfRange);
+    }
+    bit_buffer[parity] = Some(try_get_parity(bit_buffer, start, stop, odd)?);
+    Ok(())
+}
+
+/// Returns the month and day-of-month for the given day-of-year, or None if the input is invalid.
+///
+/// # Arguments
+/// * `day_of_year` - day of the year, 1 through 365 or 366
+/// * `leap_year` - if the year is a leap year
+pub fn get_month_day(day_of_year: u16, leap_year: bool) -> Option<(u8, u8)> {
+    if day_of_year == 0 || day_of_year > 365 + leap_year as u16 {
+        return None;
+    }
+    let month = (1..=12)
+        .rev()
+        .find(|m| day_of_year > days_before_month(*m, leap_year))?;
+    Some((
+        month as u8,
+        (day_of_year - days_before_month(month, leap_year)) as u8,
+    ))
+}
+
+/// Returns the day-of-year for the given month and day-of-month, or None if the input is invalid.
+///
+/// # Arguments
+/// * `month` - month, 1 through 12
+/// * `day` - day of the month
+/// * `leap_year` - if the year is a leap year
+pub fn get_day_of_year(month: u8, day: u8, leap_year: bool) -> Option<u16> {
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let day_of_year = days_before_month(month as usize, leap_year) + day as u16;
+    // Check against the first day of the next month:
+    if day_of_year > days_before_month(month as usize + 1, leap_year) {
+        None
+    } else {
+        Some(day_of_year)
+    }
+}
+
+/// Returns the day of the week of the given date in the 21st century, or None if the input is invalid.
+///
+/// # Arguments
+/// * `year` - year, truncated to two digits
+/// * `month` - month, 1 through 12
+/// * `day` - day of the month, not checked against the length of the month
+/// * `sunday` - the numeric value of Sunday, i.e. 7 for DCF77 or 0 for MSF
+pub fn get_weekday(year: u8, month: u8, day: u8, sunday: u8) -> Option<u8> {
+    if year > 99 {
+        return None;
+    }
+    get_full_year_weekday(2000 + year as u16, month, day, sunday)
+}
+
+/// Returns the day of the week of the given date in the Gregorian calendar, or None if the
+/// input is invalid.
+///
+/// # Arguments
+/// * `year` - the full year, e.g. 2024
+/// * `month` - month, 1 through 12
+/// * `day` - day of the month, not checked against the length of the month
+/// * `sunday` - the numeric value of Sunday, i.e. 7 for DCF77 or 0 for MSF
+pub fn get_full_year_weekday(year: u16, month: u8, day: u8, sunday: u8) -> Option<u8> {
+    // Offsets of the first day of each month, for Sakamoto's method:
+    const OFFSETS: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    if year == 0 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let y = year as u32 - (month < 3) as u32;
+    let weekday =
+        (y + y / 4 - y / 100 + y / 400 + OFFSETS[month as usize - 1] as u32 + day as u32) % 7;
+    if weekday == 0 {
+        Some(sunday)
+    } else {
+        Some(weekday as u8)
     }
-    Some(s_parity)
+}
+
+/// Returns if the given year is a leap year in the Gregorian calendar.
+///
+/// # Arguments
+/// * `year` - the full year, e.g. 2024
+pub fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+/// Returns if the given two-digit year is a leap year, or None if it is larger than 99.
+///
+/// Time codes which only transmit two digits of the year are assumed to be in the years
+/// 2000 through 2099, like with `get_weekday()`. Use `is_leap_year()` when the full year
+/// is known, e.g. from `RadioDateTimeUtils::get_full_year()`.
+///
+/// # Arguments
+/// * `year` - year, truncated to two digits
+pub fn is_leap_year_21st_century(year: u8) -> Option<bool> {
+    (year <= 99).then(|| is_leap_year(2000 + year as u16))
+}
+
+/// Returns the number of days since 1970-01-01 for the given date in the Gregorian calendar.
+///
+/// # Arguments
+/// * `year` - the full year, e.g. 2024
+/// * `month` - month, 1 through 12
+/// * `day` - day of the month
+pub fn days_from_civil(year: u16, month: u8, day: u8) -> i32 {
+    // Count years from March on, so the leap day is at the end of the year:
+    let y = year as i32 - (month <= 2) as i32;
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month as i32 + 9) % 12) + 2) / 5 + day as i32 - 1;
+    let day_of_era = 365 * year_of_era + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the date in the Gregorian calendar as (year, month, day) for the given number of
+/// days since 1970-01-01.
+///
+/// # Arguments
+/// * `days` - number of days since 1970-01-01
+pub fn civil_from_days(days: i32) -> (u16, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (year_of_era + era * 400 + (month <= 2) as i32) as u16;
+    (year, month, day)
+}
+
+/// Advance a date/time given as day-of-year by one minute, without any daylight saving time changes.
+///
+/// This suits time codes like WWVB which transmit the time of the current minute as day-of-year.
+/// A value which might change because of an unknown or invalid less significant value becomes None.
+///
+/// # Arguments
+/// * `year` - year, truncated to two digits
+/// * `leap_year` - if the year is a leap year
+/// * `day_of_year` - day of the year, 1 through 365 or 366
+/// * `hour` - hour, 0 through 23
+/// * `minute` - minute, 0 through 59
+pub fn add_minute_day_of_year(
+    year: &mut Option<u8>,
+    leap_year: &mut Option<bool>,
+    day_of_year: &mut Option<u16>,
+    hour: &mut Option<u8>,
+    minute: &mut Option<u8>,
+) {
+    match *minute {
+        Some(m) if m < 59 => {
+            *minute = Some(m + 1);
+            return;
+        }
+        Some(59) => *minute = Some(0),
+        _ => *hour = None,
+    }
+    match *hour {
+        Some(h) if h < 23 => {
+            *hour = Some(h + 1);
+            return;
+        }
+        Some(23) => *hour = Some(0),
+        _ => *day_of_year = None,
+    }
+    match (*day_of_year, *leap_year) {
+        (Some(d), _) if (1..365).contains(&d) => {
+            *day_of_year = Some(d + 1);
+            return;
+        }
+        (Some(365), Some(false)) | (Some(366), Some(true)) => *day_of_year = Some(1),
+        (Some(365), Some(true)) => {
+            *day_of_year = Some(366);
+            return;
+        }
+        _ => *day_of_year = None,
+    }
+    if day_of_year.is_some() && year.is_some() && year.unwrap() < 100 {
+        *year = Some((year.unwrap() + 1) % 100);
+        *leap_year = is_leap_year_21st_century(year.unwrap());
+    } else {
+        *year = None;
+        *leap_year = None;
+    }
+}
+
+/// Move a date/time given as day-of-year back by one minute, the inverse of
+/// `add_minute_day_of_year()`.
+///
+/// # Arguments
+/// * `year` - year, 0 through 99
+/// * `leap_year` - if the year is a leap year
+/// * `day_of_year` - day of the year, 1 through 365 or 366
+/// * `hour` - hour, 0 through 23
+/// * `minute` - minute, 0 through 59
+pub fn sub_minute_day_of_year(
+    year: &mut u8,
+    leap_year: &mut bool,
+    day_of_year: &mut u16,
+    hour: &mut u8,
+    minute: &mut u8,
+) {
+    if *minute > 0 {
+        *minute -= 1;
+        return;
+    }
+    *minute = 59;
+    if *hour > 0 {
+        *hour -= 1;
+        return;
+    }
+    *hour = 23;
+    if *day_of_year > 1 {
+        *day_of_year -= 1;
+        return;
+    }
+    *year = (*year + 99) % 100;
+    *leap_year = is_leap_year_21st_century(*year) == Some(true);
+    *day_of_year = 365 + *leap_year as u16;
+}
+
+/// Return the number of days in the year before the given month.
+///
+/// # Arguments
+/// * `month` - month, 1 through 13 (13 returning the length of the year)
+/// * `leap_year` - if the year is a leap year
+fn days_before_month(month: usize, leap_year: bool) -> u16 {
+    const DAYS_BEFORE: [u16; 13] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334, 365];
+    DAYS_BEFORE[month - 1] + (leap_year && month > 2) as u16
+}
+
+/// Return the total number of bits in the given groups.
+///
+/// # Arguments
+/// * `groups` - (start, stop) positions of each group of bits
+fn get_width(groups: &[(usize, usize)]) -> usize {
+    groups
+        .iter()
+        .map(|(start, stop)| start.abs_diff(*stop) + 1)
+        .sum()
 }
 
 /// Return a tuple of the two parameters in ascending order.
@@ -115,6 +703,49 @@ mod tests {
     fn test_time_diff_zero() {
         assert_eq!(time_diff(2, 2), 0);
     }
+    #[test]
+    fn test_time_diff_ticks_widths() {
+        assert_eq!(time_diff_ticks(u16::MAX - 100, 100u16), 201);
+        assert_eq!(time_diff_ticks(u32::MAX - 100, 100u32), 201);
+        assert_eq!(time_diff_ticks(u64::MAX - 100, 100u64), 201);
+        assert_eq!(time_diff_ticks(5u64, 1_000_000_000_005), 1_000_000_000_000);
+        // same as time_diff() for u32:
+        assert_eq!(
+            time_diff_ticks(u32::MAX - 100, 0u32),
+            time_diff(u32::MAX - 100, 0)
+        );
+    }
+    #[test]
+    fn test_time_diff_micros_units() {
+        assert_eq!(
+            time_diff_micros(65_000u16, 500, TimeUnit::Millisecond),
+            1_036_000
+        );
+        assert_eq!(
+            time_diff_micros(10u32, 1_000_010, TimeUnit::Microsecond),
+            1_000_000
+        );
+        assert_eq!(
+            time_diff_micros(u64::MAX - 999, 1_500_000_000, TimeUnit::Nanosecond),
+            1_500_001
+        );
+        assert_eq!(
+            time_diff_micros(0u64, u64::MAX, TimeUnit::Millisecond),
+            u64::MAX
+        );
+    }
+    #[test]
+    fn test_time_diff_checked() {
+        assert_eq!(
+            time_diff_checked(100u32, 1_100_100, TimeUnit::Microsecond, 2_000_000),
+            Some(1_100_000)
+        );
+        // probably wrapped more than once or missed events:
+        assert_eq!(
+            time_diff_checked(100u16, 50, TimeUnit::Millisecond, 2_000_000),
+            None
+        );
+    }
 
     const BIT_BUFFER: [Option<bool>; 10] = [
         Some(false),
@@ -158,6 +789,93 @@ mod tests {
         assert_eq!(get_bcd_value(&BIT_BUFFER[0..=5], 5, 0), Some(13));
     }
 
+    #[test]
+    fn ok_set_bcd_value_regular() {
+        let mut buffer = [None; 8];
+        assert!(set_bcd_value(&mut buffer, 0, 6, 59));
+        assert_eq!(get_bcd_value(&buffer, 0, 6), Some(59));
+        assert_eq!(buffer[7], None);
+    }
+    #[test]
+    fn ok_set_bcd_value_backwards() {
+        let mut buffer = [None; 8];
+        assert!(set_bcd_value(&mut buffer, 7, 0, 85));
+        assert_eq!(get_bcd_value(&buffer, 7, 0), Some(85));
+        assert_eq!(buffer[0], Some(true)); // MSB of the tens
+    }
+    #[test]
+    fn bad_set_bcd_value_does_not_fit() {
+        let mut buffer = [None; 8];
+        assert!(!set_bcd_value(&mut buffer, 0, 6, 80));
+        assert!(!set_bcd_value(&mut buffer, 0, 2, 8));
+        assert!(!set_bcd_value(&mut buffer, 0, 7, 100));
+        assert_eq!(buffer, [None; 8]);
+    }
+    #[test]
+    fn bad_set_bcd_value_too_wide() {
+        let mut buffer = [None; 9];
+        assert!(!set_bcd_value(&mut buffer, 0, 8, 1));
+        assert_eq!(buffer, [None; 9]);
+    }
+
+    #[test]
+    fn ok_get_bcd_value_u16_regular() {
+        // 0b0011_0110_0110 LSB first
+        let mut buffer = [Some(false); 16];
+        for idx in [1, 2, 5, 6, 8, 9] {
+            buffer[idx] = Some(true);
+        }
+        assert_eq!(get_bcd_value_u16(&buffer, &[(0, 11)]), Some(366));
+        assert_eq!(get_bcd_value_u16(&buffer, &[(0, 15)]), Some(366));
+    }
+    #[test]
+    fn ok_get_bcd_value_u16_groups() {
+        let mut buffer = [Some(false); 12];
+        assert!(set_bcd_value_u16(
+            &mut buffer,
+            &[(11, 8), (6, 3), (1, 0)],
+            366
+        ));
+        assert_eq!(
+            get_bcd_value_u16(&buffer, &[(11, 8), (6, 3), (1, 0)]),
+            Some(366)
+        );
+        // the skipped bits are untouched
+        assert_eq!(buffer[2], Some(false));
+        assert_eq!(buffer[7], Some(false));
+    }
+    #[test]
+    fn ok_get_bcd_value_u16_four_digits() {
+        let mut buffer = [None; 16];
+        assert!(set_bcd_value_u16(&mut buffer, &[(15, 0)], 9876));
+        assert_eq!(get_bcd_value_u16(&buffer, &[(15, 0)]), Some(9876));
+    }
+    #[test]
+    fn bad_get_bcd_value_u16_nibble() {
+        // second digit is 0b1010
+        let mut buffer = [Some(false); 8];
+        buffer[5] = Some(true);
+        buffer[7] = Some(true);
+        assert_eq!(get_bcd_value_u16(&buffer, &[(0, 7)]), None);
+    }
+    #[test]
+    fn bad_get_bcd_value_u16_none() {
+        assert_eq!(get_bcd_value_u16(&BIT_BUFFER, &[(0, 3), (6, 9)]), None);
+    }
+    #[test]
+    fn bad_get_bcd_value_u16_too_wide() {
+        let buffer = [Some(false); 20];
+        assert_eq!(get_bcd_value_u16(&buffer, &[(0, 9), (10, 16)]), None);
+    }
+    #[test]
+    fn bad_set_bcd_value_u16_does_not_fit() {
+        let mut buffer = [None; 16];
+        assert!(!set_bcd_value_u16(&mut buffer, &[(0, 9)], 400));
+        assert!(!set_bcd_value_u16(&mut buffer, &[(0, 15)], 10_000));
+        assert!(!set_bcd_value_u16(&mut buffer, &[(0, 9), (10, 16)], 1));
+        assert_eq!(buffer, [None; 16]);
+    }
+
     #[test]
     fn ok_get_parity_regular_even() {
         assert_eq!(
@@ -183,4 +901,300 @@ mod tests {
             Some(true)
         );
     }
+
+    #[test]
+    fn ok_set_parity_even() {
+        let mut buffer = [Some(true), Some(false), Some(true), None];
+        assert!(set_parity(&mut buffer, 0, 2, 3, false));
+        assert_eq!(buffer[3], Some(false));
+        assert_eq!(get_parity(&buffer, 0, 2, buffer[3]), Some(false));
+    }
+    #[test]
+    fn ok_set_parity_odd_backwards() {
+        let mut buffer = [None, Some(true), Some(false), Some(true)];
+        assert!(set_parity(&mut buffer, 3, 1, 0, true));
+        assert_eq!(buffer[0], Some(true));
+        assert_eq!(get_parity(&buffer, 3, 1, buffer[0]), Some(true));
+    }
+    #[test]
+    fn bad_set_parity_none() {
+        let mut buffer = [Some(true), None, Some(true), None];
+        assert!(!set_parity(&mut buffer, 0, 2, 3, false));
+        assert_eq!(buffer[3], None);
+        assert!(!set_parity(&mut buffer, 2, 3, 3, false));
+        assert!(!set_parity(&mut buffer, 0, 0, 4, false));
+    }
+
+    #[test]
+    fn test_try_get_bcd_value() {
+        assert_eq!(try_get_bcd_value(&BIT_BUFFER, 0, 3), Ok(2));
+        assert_eq!(
+            try_get_bcd_value(&BIT_BUFFER, 4, 8),
+            Err(DecodeError::BadNibble)
+        );
+        assert_eq!(
+            try_get_bcd_value(&BIT_BUFFER, 9, 8),
+            Err(DecodeError::MissingBit { index: 8 })
+        );
+        assert_eq!(
+            try_get_bcd_value(&BIT_BUFFER, 0, 8),
+            Err(DecodeError::RangeTooWide)
+        );
+    }
+    #[test]
+    fn test_try_get_bcd_value_u16() {
+        assert_eq!(
+            try_get_bcd_value_u16(&BIT_BUFFER, &[(0, 3), (9, 8)]),
+            Err(DecodeError::MissingBit { index: 8 })
+        );
+        assert_eq!(
+            try_get_bcd_value_u16(&BIT_BUFFER, &[(0, 7)]),
+            Err(DecodeError::BadNibble)
+        );
+    }
+    #[test]
+    fn test_try_set_bcd_value() {
+        let mut buffer = [None; 9];
+        assert_eq!(
+            try_set_bcd_value(&mut buffer, 0, 8, 1),
+            Err(DecodeError::RangeTooWide)
+        );
+        assert_eq!(
+            try_set_bcd_value(&mut buffer, 0, 5, 40),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(try_set_bcd_value(&mut buffer, 0, 5, 39), Ok(()));
+        assert_eq!(
+            try_set_bcd_value_u16(&mut buffer, &[(0, 8)], 200),
+            Err(DecodeError::OutOfRange)
+        );
+    }
+    #[test]
+    fn test_try_get_parity() {
+        assert_eq!(try_get_parity(&BIT_BUFFER, 0, 3, false), Ok(true));
+        assert_eq!(
+            try_get_parity(&BIT_BUFFER, 7, 9, false),
+            Err(DecodeError::MissingBit { index: 8 })
+        );
+    }
+    #[test]
+    fn bad_try_get_parity_past_end() {
+        assert_eq!(
+            try_get_parity(&BIT_BUFFER, 0, BIT_BUFFER.len(), false),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(
+            get_parity(&BIT_BUFFER, 0, BIT_BUFFER.len(), Some(false)),
+            None
+        );
+    }
+    #[test]
+    fn test_check_parity() {
+        // bits 1 and 4 are set
+        assert_eq!(check_parity(&BIT_BUFFER, 0, 3, 4, false), Ok(()));
+        assert_eq!(
+            check_parity(&BIT_BUFFER, 0, 3, 4, true),
+            Err(DecodeError::ParityMismatch)
+        );
+        assert_eq!(
+            check_parity(&BIT_BUFFER, 0, 3, 8, false),
+            Err(DecodeError::MissingBit { index: 8 })
+        );
+    }
+    #[test]
+    fn test_try_set_parity() {
+        let mut buffer = [Some(true), None, Some(true), None];
+        assert_eq!(
+            try_set_parity(&mut buffer, 0, 2, 3, false),
+            Err(DecodeError::MissingBit { index: 1 })
+        );
+        assert_eq!(
+            try_set_parity(&mut buffer, 3, 3, 3, false),
+            Err(DecodeError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn ok_get_month_day_regular() {
+        assert_eq!(get_month_day(32, false), Some((2, 1)));
+    }
+    #[test]
+    fn ok_get_month_day_leap_day() {
+        assert_eq!(get_month_day(60, true), Some((2, 29)));
+    }
+    #[test]
+    fn ok_get_month_day_no_leap_day() {
+        assert_eq!(get_month_day(60, false), Some((3, 1)));
+    }
+    #[test]
+    fn ok_get_month_day_last_day_leap() {
+        assert_eq!(get_month_day(366, true), Some((12, 31)));
+    }
+    #[test]
+    fn bad_get_month_day_too_large() {
+        assert_eq!(get_month_day(366, false), None);
+    }
+    #[test]
+    fn bad_get_month_day_zero() {
+        assert_eq!(get_month_day(0, true), None);
+    }
+
+    #[test]
+    fn ok_get_weekday_sunday7() {
+        assert_eq!(get_weekday(0, 2, 6, 7), Some(7)); // Sunday 2000-02-06
+    }
+    #[test]
+    fn ok_get_weekday_sunday0() {
+        assert_eq!(get_weekday(0, 2, 6, 0), Some(0));
+    }
+    #[test]
+    fn ok_get_weekday_regular() {
+        assert_eq!(get_weekday(24, 1, 25, 7), Some(4)); // Thursday 2024-01-25
+    }
+    #[test]
+    fn bad_get_weekday_month() {
+        assert_eq!(get_weekday(24, 13, 25, 7), None);
+    }
+
+    #[test]
+    fn ok_add_minute_day_of_year_regular() {
+        let (mut y, mut l, mut d, mut h, mut m) =
+            (Some(24), Some(true), Some(60), Some(8), Some(15));
+        add_minute_day_of_year(&mut y, &mut l, &mut d, &mut h, &mut m);
+        assert_eq!(
+            (y, l, d, h, m),
+            (Some(24), Some(true), Some(60), Some(8), Some(16))
+        );
+    }
+    #[test]
+    fn ok_add_minute_day_of_year_leap_day() {
+        let (mut y, mut l, mut d, mut h, mut m) =
+            (Some(24), Some(true), Some(365), Some(23), Some(59));
+        add_minute_day_of_year(&mut y, &mut l, &mut d, &mut h, &mut m);
+        assert_eq!(
+            (y, l, d, h, m),
+            (Some(24), Some(true), Some(366), Some(0), Some(0))
+        );
+    }
+    #[test]
+    fn ok_add_minute_day_of_year_new_year() {
+        let (mut y, mut l, mut d, mut h, mut m) =
+            (Some(99), Some(false), Some(365), Some(23), Some(59));
+        add_minute_day_of_year(&mut y, &mut l, &mut d, &mut h, &mut m);
+        assert_eq!(
+            (y, l, d, h, m),
+            (Some(0), Some(true), Some(1), Some(0), Some(0))
+        );
+    }
+    #[test]
+    fn bad_add_minute_day_of_year_none_minute() {
+        let (mut y, mut l, mut d, mut h, mut m) = (Some(24), Some(true), Some(60), Some(8), None);
+        add_minute_day_of_year(&mut y, &mut l, &mut d, &mut h, &mut m);
+        assert_eq!((y, l, d, h, m), (None, None, None, None, None));
+    }
+    #[test]
+    fn bad_add_minute_day_of_year_invalid_day() {
+        let (mut y, mut l, mut d, mut h, mut m) =
+            (Some(23), Some(false), Some(366), Some(23), Some(59));
+        add_minute_day_of_year(&mut y, &mut l, &mut d, &mut h, &mut m);
+        assert_eq!((y, l, d, h, m), (None, None, None, Some(0), Some(0)));
+    }
+
+    #[test]
+    fn ok_get_day_of_year_leap_day() {
+        assert_eq!(get_day_of_year(2, 29, true), Some(60));
+    }
+    #[test]
+    fn ok_get_day_of_year_last_day() {
+        assert_eq!(get_day_of_year(12, 31, false), Some(365));
+    }
+    #[test]
+    fn bad_get_day_of_year_no_leap_day() {
+        assert_eq!(get_day_of_year(2, 29, false), None);
+    }
+    #[test]
+    fn bad_get_day_of_year_month() {
+        assert_eq!(get_day_of_year(0, 1, false), None);
+    }
+
+    #[test]
+    fn ok_get_full_year_weekday_20th_century() {
+        assert_eq!(get_full_year_weekday(1999, 12, 31, 7), Some(5)); // Friday 1999-12-31
+    }
+    #[test]
+    fn ok_get_full_year_weekday_22nd_century() {
+        assert_eq!(get_full_year_weekday(2100, 3, 1, 0), Some(1)); // Monday 2100-03-01
+    }
+    #[test]
+    fn bad_get_full_year_weekday_year_zero() {
+        assert_eq!(get_full_year_weekday(0, 3, 1, 0), None);
+    }
+
+    #[test]
+    fn test_is_leap_year_21st_century() {
+        assert_eq!(is_leap_year_21st_century(0), Some(true));
+        assert_eq!(is_leap_year_21st_century(24), Some(true));
+        assert_eq!(is_leap_year_21st_century(99), Some(false));
+        assert_eq!(is_leap_year_21st_century(100), None);
+    }
+    #[test]
+    fn test_is_leap_year() {
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(2023));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2100));
+    }
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+    #[test]
+    fn test_sub_minute_day_of_year() {
+        let (mut year, mut leap_year, mut day_of_year, mut hour, mut minute) = (24, true, 60, 0, 0);
+        sub_minute_day_of_year(
+            &mut year,
+            &mut leap_year,
+            &mut day_of_year,
+            &mut hour,
+            &mut minute,
+        );
+        assert_eq!(
+            (year, leap_year, day_of_year, hour, minute),
+            (24, true, 59, 23, 59)
+        );
+    }
+    #[test]
+    fn test_sub_minute_day_of_year_new_year() {
+        let (mut year, mut leap_year, mut day_of_year, mut hour, mut minute) = (25, false, 1, 0, 0);
+        sub_minute_day_of_year(
+            &mut year,
+            &mut leap_year,
+            &mut day_of_year,
+            &mut hour,
+            &mut minute,
+        );
+        assert_eq!(
+            (year, leap_year, day_of_year, hour, minute),
+            (24, true, 366, 23, 59)
+        );
+        let (mut year, mut leap_year, mut day_of_year, mut hour, mut minute) = (0, true, 1, 0, 0);
+        sub_minute_day_of_year(
+            &mut year,
+            &mut leap_year,
+            &mut day_of_year,
+            &mut hour,
+            &mut minute,
+        );
+        assert_eq!((year, leap_year, day_of_year), (99, false, 365));
+    }
 }
@@ -0,0 +1,295 @@
+//! Decoder for the time code of RBU (Taldom, Russia, 66.6 kHz).
+//!
+//! RBU transmits Moscow time (UTC+3) without daylight saving time. Each second carries
+//! two symbols, a bit is transmitted as a pair of complementary symbols so that a
+//! corrupted pair can be detected. The bits, indexed by second, are:
+//!
+//! * 0: the minute marker
+//! * 1 through 16: DUT1, with bits 1 through 8 counting positive and bits 9 through 16
+//!   counting negative tenths of a second
+//! * 17 through 24: year, 25 through 29: month, 30 through 35: day of the month
+//! * 36 through 38: day of the week, Monday being 1 and Sunday being 7
+//! * 39 through 44: hour, 45 through 51: minute
+//! * 52: even parity over bits 17 through 38, 53: even parity over bits 39 through 51
+//! * 54: leap second warning, 55: leap second sign, 56 through 59: unused, always 0
+//!
+//! All values are BCD-encoded, most significant bit first. Like with DCF77 and MSF, the
+//! time code describes the minute starting at the end of it.
+
+use crate::msf::get_dut1;
+use crate::radio_datetime_helpers::{get_bcd_value, get_parity, set_bcd_value};
+use crate::utc::get_minute_length;
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, LEAP_ANNOUNCED, LEAP_NEGATIVE, LEAP_PROCESSED,
+};
+
+/// Symbol pairs of one minute, indexed by second.
+pub type SymbolBuffer = [(Option<bool>, Option<bool>); BIT_BUFFER_SIZE];
+
+/// Per-field outcome of decoding one RBU minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub leap_second: FieldStatus,
+    /// DUT1 in units of 0.1 second, None if absent or inconsistent
+    pub dut1: Option<i8>,
+}
+
+/// Returns the bits transmitted by the given symbol pairs.
+///
+/// A pair of (true, false) is a 1 bit and a pair of (false, true) is a 0 bit, other pairs
+/// yield None bits.
+///
+/// # Arguments
+/// * `symbols` - buffer containing the symbol pairs
+pub fn get_bit_buffer(symbols: &SymbolBuffer) -> [Option<bool>; BIT_BUFFER_SIZE] {
+    let mut bit_buffer = [None; BIT_BUFFER_SIZE];
+    for (bit, pair) in bit_buffer.iter_mut().zip(symbols) {
+        *bit = match *pair {
+            (Some(first), Some(second)) if first != second => Some(first),
+            _ => None,
+        };
+    }
+    bit_buffer
+}
+
+/// Returns the symbol pairs transmitting the given bits, the inverse of `get_bit_buffer()`.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits
+pub fn get_symbols(bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE]) -> SymbolBuffer {
+    let mut symbols = [(None, None); BIT_BUFFER_SIZE];
+    for (pair, bit) in symbols.iter_mut().zip(bit_buffer) {
+        *pair = (*bit, bit.map(|b| !b));
+    }
+    symbols
+}
+
+/// Decode the bit buffer of one RBU minute into the given date/time instance.
+///
+/// The date/time is first advanced by one minute using `add_minute()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(7)`
+/// * `bit_buffer` - the bits of the minute, indexed by second, as obtained from
+///   `get_bit_buffer()`
+/// * `minute_length` - the length of the decoded minute in seconds, 59 through 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    let added_minute = rdt.add_minute();
+    let check_jump = check_jump && added_minute;
+    let frame_ok = (59..=61).contains(&minute_length)
+        && bit_buffer[56..=58].iter().all(|bit| *bit == Some(false));
+
+    // Both parities are even:
+    let parity_date = get_parity(bit_buffer, 17, 38, bit_buffer[52]).map(|p| !p);
+    let parity_time = get_parity(bit_buffer, 39, 51, bit_buffer[53]).map(|p| !p);
+
+    let minute = get_bcd_value(bit_buffer, 51, 45).filter(|_| frame_ok);
+    rdt.set_minute(minute, parity_time == Some(true), check_jump);
+    let hour = get_bcd_value(bit_buffer, 44, 39).filter(|_| frame_ok);
+    rdt.set_hour(hour, parity_time == Some(true), check_jump);
+    let year = get_bcd_value(bit_buffer, 24, 17).filter(|_| frame_ok);
+    rdt.set_year(year, parity_date == Some(true), check_jump);
+    let month = get_bcd_value(bit_buffer, 29, 25).filter(|_| frame_ok);
+    rdt.set_month(month, parity_date == Some(true), check_jump);
+    let weekday = get_bcd_value(bit_buffer, 38, 36).filter(|_| frame_ok);
+    rdt.set_weekday(weekday, parity_date == Some(true), check_jump);
+    // day must be set *after* year, month, and weekday
+    let day = get_bcd_value(bit_buffer, 35, 30).filter(|_| frame_ok);
+    rdt.set_day(day, parity_date == Some(true), check_jump);
+
+    // Russia does not observe daylight saving time:
+    rdt.set_dst(Some(false), Some(false), check_jump);
+    rdt.set_signed_leap_second(
+        bit_buffer[54].filter(|_| frame_ok),
+        bit_buffer[55],
+        minute_length,
+    );
+    rdt.bump_minutes_running();
+
+    DecodeReport {
+        minute: FieldStatus::new(&bit_buffer[45..=51], parity_time, minute, rdt.get_minute()),
+        hour: FieldStatus::new(&bit_buffer[39..=44], parity_time, hour, rdt.get_hour()),
+        day: FieldStatus::new(&bit_buffer[30..=35], parity_date, day, rdt.get_day()),
+        weekday: FieldStatus::new(
+            &bit_buffer[36..=38],
+            parity_date,
+            weekday,
+            rdt.get_weekday(),
+        ),
+        month: FieldStatus::new(&bit_buffer[25..=29], parity_date, month, rdt.get_month()),
+        year: FieldStatus::new(&bit_buffer[17..=24], parity_date, year, rdt.get_year()),
+        leap_second: FieldStatus::new(
+            &bit_buffer[54..=55],
+            Some(true),
+            Some(minute_length).filter(|m| (59..=61).contains(m)),
+            Some(minute_length),
+        ),
+        dut1: get_dut1(bit_buffer, 17),
+    }
+}
+
+/// Encode the given date/time into the bit buffer of one RBU minute, the inverse of
+/// `decode_time()`.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid. DUT1 is encoded as 0, the minute marker and unused bits at
+/// the end are None.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<bool>; BIT_BUFFER_SIZE], u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let minute = rdt.get_minute().unwrap();
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    let minute_length = get_minute_length(leap_second, minute);
+    let mut bit_buffer = [None; BIT_BUFFER_SIZE];
+    for bit in &mut bit_buffer[1..minute_length as usize] {
+        *bit = Some(false);
+    }
+    set_bcd_value(&mut bit_buffer, 24, 17, rdt.get_year().unwrap());
+    set_bcd_value(&mut bit_buffer, 29, 25, rdt.get_month().unwrap());
+    set_bcd_value(&mut bit_buffer, 35, 30, rdt.get_day().unwrap());
+    // Sunday is 7:
+    let weekday = rdt.get_weekday().unwrap();
+    set_bcd_value(
+        &mut bit_buffer,
+        38,
+        36,
+        if weekday == 0 { 7 } else { weekday },
+    );
+    set_bcd_value(&mut bit_buffer, 44, 39, rdt.get_hour().unwrap());
+    set_bcd_value(&mut bit_buffer, 51, 45, minute);
+    bit_buffer[52] = get_parity(&bit_buffer, 17, 38, Some(false));
+    bit_buffer[53] = get_parity(&bit_buffer, 39, 51, Some(false));
+    // Announcements last until the end of the minute before the change:
+    bit_buffer[54] =
+        Some(leap_second & LEAP_ANNOUNCED != 0 || minute == 0 && leap_second & LEAP_PROCESSED != 0);
+    bit_buffer[55] = Some(leap_second & LEAP_NEGATIVE != 0);
+    Some((bit_buffer, minute_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rdt_at;
+    use crate::utc::get_utc;
+    use crate::Station;
+
+    // Sunday 2024-06-16 15:47 MSK, DUT1 = -0.3 s
+    fn frame() -> [Option<bool>; BIT_BUFFER_SIZE] {
+        let (mut buffer, _) = encode_time(&rdt_at(7, (24, 6, 16), (15, 47), false)).unwrap();
+        for bit in &mut buffer[9..=11] {
+            *bit = Some(true);
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let bits = get_bit_buffer(&get_symbols(&frame()));
+        let report = decode_time(&mut rdt, &bits, 60, false);
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_month(), Some(6));
+        assert_eq!(rdt.get_day(), Some(16));
+        assert_eq!(rdt.get_weekday(), Some(7));
+        assert_eq!(rdt.get_hour(), Some(15));
+        assert_eq!(rdt.get_minute(), Some(47));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(report.weekday, FieldStatus::Ok);
+        assert_eq!(report.dut1, Some(-3));
+        let utc = get_utc(&rdt, Station::Rbu, 0).unwrap();
+        assert_eq!((utc.hour, utc.minute), (12, 47));
+    }
+    #[test]
+    fn test_decode_time_bad_parity() {
+        let mut bits = frame();
+        bits[51] = Some(false);
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let report = decode_time(&mut rdt, &bits, 60, false);
+        assert_eq!(rdt.get_minute(), None);
+        assert_eq!(report.minute, FieldStatus::BadParity);
+        assert_eq!(report.day, FieldStatus::Ok);
+    }
+    #[test]
+    fn test_get_bit_buffer_bad_symbols() {
+        let mut symbols = get_symbols(&frame());
+        symbols[40] = (Some(true), Some(true));
+        symbols[41] = (None, Some(false));
+        let bits = get_bit_buffer(&symbols);
+        assert_eq!(bits[39], Some(false));
+        assert_eq!(bits[40], None);
+        assert_eq!(bits[41], None);
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let report = decode_time(&mut rdt, &bits, 60, false);
+        assert_eq!(rdt.get_hour(), None);
+        assert_eq!(report.hour, FieldStatus::Missing);
+    }
+    #[test]
+    fn test_encode_time_regular() {
+        let (bits, minute_length) = encode_time(&rdt_at(7, (24, 6, 16), (15, 47), false)).unwrap();
+        assert_eq!(minute_length, 60);
+        assert_eq!(bits[0], None);
+        assert_eq!(get_bcd_value(&bits, 24, 17), Some(24));
+        assert_eq!(get_bcd_value(&bits, 29, 25), Some(6));
+        assert_eq!(get_bcd_value(&bits, 35, 30), Some(16));
+        assert_eq!(get_bcd_value(&bits, 38, 36), Some(7));
+        assert_eq!(get_bcd_value(&bits, 44, 39), Some(15));
+        assert_eq!(get_bcd_value(&bits, 51, 45), Some(47));
+        assert_eq!(get_parity(&bits, 17, 38, bits[52]), Some(false));
+        assert_eq!(get_parity(&bits, 39, 51, bits[53]), Some(false));
+        // DUT1 is encoded as 0:
+        assert!(bits[1..=16].iter().all(|bit| *bit == Some(false)));
+        assert!(bits[54..=59].iter().all(|bit| *bit == Some(false)));
+        assert_eq!(bits[60..], [None, None]);
+    }
+    #[test]
+    fn continue_decode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(7);
+        decode_time(&mut rdt, &frame(), 60, false);
+        // 2024-07-01 02:59 MSK, announcing the leap second:
+        rdt.set_minute(Some(59), true, false);
+        rdt.set_hour(Some(2), true, false);
+        rdt.set_month(Some(7), true, false);
+        rdt.set_weekday(Some(1), true, false);
+        rdt.set_day(Some(1), true, false);
+        rdt.set_signed_leap_second(Some(true), Some(true), 60);
+        // 2024-07-01 03:00 MSK is 2024-07-01 00:00 UTC:
+        rdt.set_minute(Some(0), true, false);
+        rdt.set_hour(Some(3), true, false);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED | LEAP_NEGATIVE));
+        let (bits, minute_length) = encode_time(&rdt).unwrap();
+        assert_eq!(minute_length, 59);
+        assert_eq!(bits[54..=55], [Some(true), Some(true)]);
+        let mut decoded = rdt;
+        decoded.set_minute(Some(59), true, false);
+        decoded.set_hour(Some(2), true, false);
+        decode_time(&mut decoded, &bits, minute_length, true);
+        assert_eq!(decoded.get_hour(), Some(3));
+        assert_eq!(decoded.get_minute(), Some(0));
+        assert_eq!(
+            decoded.get_leap_second(),
+            Some(LEAP_PROCESSED | LEAP_NEGATIVE)
+        );
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(7)), None);
+    }
+}
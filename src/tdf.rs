@@ -0,0 +1,71 @@
+//! Decoder for the time code of TDF/ALS162 (Allouis, France, 162 kHz).
+//!
+//! TDF transmits CET or CEST by phase-modulating the carrier, one bit per second with no
+//! modulation in the last second of the minute. The bits use the layout of DCF77, with
+//! bits 1 through 14 unused, so decoding and encoding are done by the DCF77 functions.
+//! Like with DCF77, Sunday is 7.
+
+use crate::{dcf77, RadioDateTimeUtils, BIT_BUFFER_SIZE};
+
+/// Per-field outcome of decoding one TDF minute.
+pub type DecodeReport = dcf77::DecodeReport;
+
+/// Decode the bit buffer of one TDF minute into the given date/time instance.
+///
+/// The date/time is first advanced by one minute using `add_minute()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(7)`
+/// * `bit_buffer` - the bits of the minute, indexed by second
+/// * `minute_length` - the length of the decoded minute in seconds, 60 or 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    dcf77::decode_time(rdt, bit_buffer, minute_length, check_jump)
+}
+
+/// Encode the given date/time into the bit buffer of one TDF minute, the inverse of
+/// `decode_time()`.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid. The minute marker and any unused bits at the end are None.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<bool>; BIT_BUFFER_SIZE], u8)> {
+    dcf77::encode_time(rdt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utc::get_utc;
+    use crate::{FieldStatus, Station, DST_SUMMER};
+
+    #[test]
+    fn test_encode_time_round_trip() {
+        let mut expected = RadioDateTimeUtils::new(7);
+        expected.set_year(Some(24), true, false);
+        expected.set_month(Some(6), true, false);
+        expected.set_weekday(Some(7), true, false);
+        expected.set_day(Some(16), true, false);
+        expected.set_hour(Some(12), true, false);
+        expected.set_minute(Some(34), true, false);
+        expected.set_dst(Some(true), Some(false), false);
+        let (bits, minute_length) = encode_time(&expected).unwrap();
+        assert!(bits[1..=14].iter().all(|b| *b == Some(false)));
+        let mut rdt = RadioDateTimeUtils::new(7);
+        let report = decode_time(&mut rdt, &bits, minute_length, false);
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(rdt.get_weekday(), Some(7));
+        assert_eq!(rdt.get_minute(), Some(34));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+        let utc = get_utc(&rdt, Station::Tdf, 0).unwrap();
+        assert_eq!((utc.hour, utc.minute), (10, 34));
+    }
+}
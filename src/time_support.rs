@@ -0,0 +1,125 @@
+//! Conversions between `RadioDateTimeUtils` and the date/time types of the `time` crate.
+
+use crate::utc::{from_local_minutes, from_utc_minutes, is_summer, StationDateTime};
+use crate::{ConversionError, RadioDateTimeUtils, Station};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+/// Converts into the local time of the station, at the start of the minute.
+impl TryFrom<&RadioDateTimeUtils> for PrimitiveDateTime {
+    type Error = ConversionError;
+
+    fn try_from(rdt: &RadioDateTimeUtils) -> Result<Self, Self::Error> {
+        if !rdt.is_valid() {
+            return Err(ConversionError);
+        }
+        let date = Date::from_calendar_date(
+            rdt.get_full_year().ok_or(ConversionError)? as i32,
+            Month::try_from(rdt.get_month().unwrap()).map_err(|_| ConversionError)?,
+            rdt.get_day().unwrap(),
+        )
+        .map_err(|_| ConversionError)?;
+        let time = Time::from_hms(rdt.get_hour().unwrap(), rdt.get_minute().unwrap(), 0)
+            .map_err(|_| ConversionError)?;
+        Ok(Self::new(date, time))
+    }
+}
+
+/// Converts into the local time of the station, with its offset to UTC determined by the
+/// station and the DST bitmask.
+impl TryFrom<StationDateTime<'_>> for OffsetDateTime {
+    type Error = ConversionError;
+
+    fn try_from(value: StationDateTime) -> Result<Self, Self::Error> {
+        let StationDateTime { rdt, station } = value;
+        let local = PrimitiveDateTime::try_from(rdt)?;
+        let summer = is_summer(rdt.get_dst().unwrap(), rdt.get_minute().unwrap());
+        let offset = UtcOffset::from_whole_seconds(station.get_utc_offset(summer) as i32 * 60)
+            .map_err(|_| ConversionError)?;
+        Ok(local.assume_offset(offset))
+    }
+}
+
+/// Converts from a date/time with any offset into the local time of the station, with
+/// daylight saving time determined by `utc::is_summer_at()`. Seconds are discarded.
+impl TryFrom<(OffsetDateTime, Station)> for RadioDateTimeUtils {
+    type Error = ConversionError;
+
+    fn try_from(value: (OffsetDateTime, Station)) -> Result<Self, Self::Error> {
+        let (value, station) = value;
+        from_utc_minutes(station, value.unix_timestamp().div_euclid(60))
+    }
+}
+
+/// Converts from the local time of the station, with daylight saving time determined by
+/// `utc::is_summer_at()`. Seconds are discarded.
+///
+/// Fails for a local time which does not exist or is ambiguous because of a DST change.
+impl TryFrom<(PrimitiveDateTime, Station)> for RadioDateTimeUtils {
+    type Error = ConversionError;
+
+    fn try_from(value: (PrimitiveDateTime, Station)) -> Result<Self, Self::Error> {
+        let (value, station) = value;
+        from_local_minutes(station, value.assume_utc().unix_timestamp().div_euclid(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DST_SUMMER;
+
+    fn date_time(year: i32, month: Month, day: u8, hour: u8, minute: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(year, month, day).unwrap(),
+            Time::from_hms(hour, minute, 0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_primitive_date_time() {
+        let value = date_time(2024, Month::February, 29, 23, 59);
+        let rdt = RadioDateTimeUtils::try_from((value, Station::Wwvb)).unwrap();
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(PrimitiveDateTime::try_from(&rdt), Ok(value));
+    }
+    #[test]
+    fn bad_primitive_date_time_dst_change() {
+        // 01:30 occurs twice on 2024-10-27 in the United Kingdom
+        let value = date_time(2024, Month::October, 27, 1, 30);
+        assert_eq!(
+            RadioDateTimeUtils::try_from((value, Station::Msf)).err(),
+            Some(ConversionError)
+        );
+    }
+    #[test]
+    fn test_offset_date_time() {
+        // 2024-07-04 12:00 UTC
+        let value = date_time(2024, Month::July, 4, 12, 0).assume_utc();
+        let rdt = RadioDateTimeUtils::try_from((value, Station::Msf)).unwrap();
+        assert_eq!(rdt.get_hour(), Some(13));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+        let bst = OffsetDateTime::try_from(StationDateTime::new(&rdt, Station::Msf)).unwrap();
+        assert_eq!(bst.offset(), UtcOffset::from_hms(1, 0, 0).unwrap());
+        assert_eq!(bst, value);
+        assert_eq!(bst.unix_timestamp(), 1_720_094_400);
+
+        let rdt = RadioDateTimeUtils::try_from((bst, Station::Bpc)).unwrap();
+        assert_eq!(rdt.get_hour(), Some(20));
+        assert_eq!(rdt.get_dst(), Some(0));
+        // WWVB transmits UTC with the DST state of the USA:
+        let rdt = RadioDateTimeUtils::try_from((bst, Station::Wwvb)).unwrap();
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn bad_primitive_date_time() {
+        let rdt = RadioDateTimeUtils::new(0);
+        assert_eq!(PrimitiveDateTime::try_from(&rdt), Err(ConversionError));
+        assert_eq!(
+            OffsetDateTime::try_from(StationDateTime::new(&rdt, Station::Wwvb)),
+            Err(ConversionError)
+        );
+    }
+}
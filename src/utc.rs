@@ -0,0 +1,464 @@
+//! Conversion of decoded date/time values to UTC and to Unix timestamps.
+
+use crate::radio_datetime_helpers::{civil_from_days, days_from_civil, get_full_year_weekday};
+#[cfg(any(feature = "chrono", feature = "time"))]
+use crate::{ConversionError, LocalDateTime};
+use crate::{
+    RadioDateTimeUtils, Station, DST_ANNOUNCED, DST_SUMMER, LEAP_ANNOUNCED, LEAP_MISSING,
+    LEAP_NEGATIVE, LEAP_PROCESSED,
+};
+
+/// Broken-down date and time in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UtcDateTime {
+    /// full year, e.g. 2024
+    pub year: u16,
+    /// month, 1 through 12
+    pub month: u8,
+    /// day of the month, 1 through 31
+    pub day: u8,
+    /// hour, 0 through 23
+    pub hour: u8,
+    /// minute, 0 through 59
+    pub minute: u8,
+    /// second, 0 through 60 where 60 is a positive leap second
+    pub second: u8,
+}
+
+/// A decoded date/time together with the station which transmitted it, so it can be
+/// converted into date/time types carrying an offset to UTC.
+#[derive(Clone, Copy)]
+pub struct StationDateTime<'a> {
+    pub rdt: &'a RadioDateTimeUtils,
+    pub station: Station,
+}
+
+impl<'a> StationDateTime<'a> {
+    /// Initialize a new StationDateTime instance
+    ///
+    /// # Arguments
+    /// * `rdt` - the decoded date/time
+    /// * `station` - the station which transmitted the date/time
+    pub fn new(rdt: &'a RadioDateTimeUtils, station: Station) -> Self {
+        Self { rdt, station }
+    }
+}
+
+/// Returns if daylight saving time is in effect for the current date/time.
+///
+/// `add_minute()` already shifts the hour at a DST transition, but the DST_SUMMER flag is
+/// only updated by `set_dst()`. An announcement which is still pending at the start of an
+/// hour therefore means the flag is about to flip.
+pub(crate) fn is_summer(dst: u8, minute: u8) -> bool {
+    let summer = dst & DST_SUMMER != 0;
+    if minute == 0 && dst & DST_ANNOUNCED != 0 {
+        !summer
+    } else {
+        summer
+    }
+}
+
+/// Returns the length in seconds of the minute ending at the current date/time, which is
+/// 61 for a positive leap second and 59 for a negative one.
+///
+/// Like with the DST flags, `add_minute()` does not process an announced leap second.
+pub(crate) fn get_minute_length(leap_second: u8, minute: u8) -> u8 {
+    let leap_minute = minute == 0
+        && (leap_second & LEAP_ANNOUNCED != 0
+            || leap_second & (LEAP_PROCESSED | LEAP_MISSING) == LEAP_PROCESSED);
+    match (leap_minute, leap_second & LEAP_NEGATIVE != 0) {
+        (false, _) => 60,
+        (true, false) => 61,
+        (true, true) => 59,
+    }
+}
+
+/// Convert the date/time transmitted by the given station to UTC.
+///
+/// Returns None if the date/time is not valid, the century cannot be determined, or if
+/// `second` is beyond the end of the minute, the length of which is determined like with
+/// `get_minute_length()` for the minute ending at the next minute 0. An unknown leap
+/// second state counts as no leap second, as not every station announces them.
+///
+/// # Arguments
+/// * `rdt` - the decoded date/time
+/// * `station` - the station which transmitted the date/time, determines the time zone
+/// * `second` - the second within the minute, 0 through 60
+pub fn get_utc(rdt: &RadioDateTimeUtils, station: Station, second: u8) -> Option<UtcDateTime> {
+    if !rdt.is_valid() || second > 60 {
+        return None;
+    }
+    let minute = rdt.get_minute()?;
+    // The leap second is at the end of the minute before minute 0:
+    if second >= get_minute_length(rdt.get_leap_second().unwrap_or(0), (minute + 1) % 60) {
+        return None;
+    }
+    let year = rdt.get_full_year()?;
+    let offset = station.get_utc_offset(is_summer(rdt.get_dst()?, minute)) as i32;
+    let local = days_from_civil(year, rdt.get_month()?, rdt.get_day()?) * 1440
+        + rdt.get_hour()? as i32 * 60
+        + minute as i32;
+    Some(from_minutes(local - offset, second))
+}
+
+/// Returns if daylight saving time is in effect at the given UTC date/time, according to
+/// the time zone rules of the given station. The second is ignored.
+///
+/// DCF77, TDF, and MSF follow the European rule, with summer time from 01:00 UTC on the
+/// last Sunday of March until 01:00 UTC on the last Sunday of October. For WWVB, the US
+/// rule is applied at 00:00 UTC like its DST bits are, so summer time starts at the end
+/// of the second Sunday of March and ends at the end of the first Sunday of November.
+/// The other stations do not use daylight saving time.
+///
+/// # Arguments
+/// * `station` - the station whose time zone rules to use
+/// * `utc` - the date/time in UTC
+pub fn is_summer_at(station: Station, utc: &UtcDateTime) -> bool {
+    let year = utc.year;
+    let minutes = to_minutes(utc);
+    match station {
+        Station::Dcf77 | Station::Msf | Station::Tdf => {
+            let start = days_from_civil(year, 3, get_sunday_from(year, 3, 25)) * 1440 + 60;
+            let end = days_from_civil(year, 10, get_sunday_from(year, 10, 25)) * 1440 + 60;
+            (start..end).contains(&minutes)
+        }
+        Station::Wwvb => {
+            let start = days_from_civil(year, 3, get_sunday_from(year, 3, 8)) + 1;
+            let end = days_from_civil(year, 11, get_sunday_from(year, 11, 1)) + 1;
+            (start * 1440..end * 1440).contains(&minutes)
+        }
+        Station::Jjy | Station::Bpc | Station::Rbu => false,
+    }
+}
+
+/// Returns a new date/time in the local time of the station for the given number of
+/// minutes since 1970-01-01 00:00 UTC, with DST determined by `is_summer_at()`.
+///
+/// # Arguments
+/// * `station` - the station whose local time to use
+/// * `minutes` - the number of minutes since 1970-01-01 00:00 UTC
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn from_utc_minutes(
+    station: Station,
+    minutes: i64,
+) -> Result<RadioDateTimeUtils, ConversionError> {
+    let minutes = i32::try_from(minutes).map_err(|_| ConversionError)?;
+    let summer = is_summer_at(station, &from_minutes(minutes, 0));
+    seed_local(
+        station,
+        minutes + station.get_utc_offset(summer) as i32,
+        summer,
+    )
+}
+
+/// Returns a new date/time for the given number of minutes since 1970-01-01 00:00 in the
+/// local time of the station, with DST determined by `is_summer_at()`.
+///
+/// Returns an error if the local time does not exist or is ambiguous because of a DST
+/// change.
+///
+/// # Arguments
+/// * `station` - the station whose local time is used
+/// * `minutes` - the number of minutes since 1970-01-01 00:00 local time
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub(crate) fn from_local_minutes(
+    station: Station,
+    minutes: i64,
+) -> Result<RadioDateTimeUtils, ConversionError> {
+    let minutes = i32::try_from(minutes).map_err(|_| ConversionError)?;
+    let mut summer = [false, true].into_iter().filter(|summer| {
+        let utc = from_minutes(minutes - station.get_utc_offset(*summer) as i32, 0);
+        is_summer_at(station, &utc) == *summer
+    });
+    match (summer.next(), summer.next()) {
+        (Some(summer), None) => seed_local(station, minutes, summer),
+        _ => Err(ConversionError),
+    }
+}
+
+/// Returns a new date/time for the given local time of the station.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn seed_local(
+    station: Station,
+    minutes: i32,
+    summer: bool,
+) -> Result<RadioDateTimeUtils, ConversionError> {
+    let local = from_minutes(minutes, 0);
+    let mut rdt = RadioDateTimeUtils::new(station.get_sunday());
+    rdt.seed(&LocalDateTime {
+        year: local.year,
+        month: local.month,
+        day: local.day,
+        hour: local.hour,
+        minute: local.minute,
+        summer,
+    })?;
+    Ok(rdt)
+}
+
+/// Returns the date/time for the given number of minutes since 1970-01-01 00:00.
+fn from_minutes(minutes: i32, second: u8) -> UtcDateTime {
+    let (year, month, day) = civil_from_days(minutes.div_euclid(1440));
+    let minute_of_day = minutes.rem_euclid(1440);
+    UtcDateTime {
+        year,
+        month,
+        day,
+        hour: (minute_of_day / 60) as u8,
+        minute: (minute_of_day % 60) as u8,
+        second,
+    }
+}
+
+/// Returns the number of minutes since 1970-01-01 00:00 for the given date/time.
+fn to_minutes(value: &UtcDateTime) -> i32 {
+    days_from_civil(value.year, value.month, value.day) * 1440
+        + value.hour as i32 * 60
+        + value.minute as i32
+}
+
+/// Returns the day of the month of the first Sunday on or after the given day.
+fn get_sunday_from(year: u16, month: u8, day: u8) -> u8 {
+    day + (7 - get_full_year_weekday(year, month, day, 7).unwrap_or(7)) % 7
+}
+
+/// Convert the date/time transmitted by the given station to a Unix timestamp, i.e. the
+/// number of seconds since 1970-01-01 00:00:00 UTC.
+///
+/// As Unix time does not count leap seconds, a positive leap second yields the same
+/// timestamp as the first second of the next minute.
+///
+/// # Arguments
+/// * `rdt` - the decoded date/time
+/// * `station` - the station which transmitted the date/time, determines the time zone
+/// * `second` - the second within the minute, 0 through 60
+pub fn get_unix_timestamp(rdt: &RadioDateTimeUtils, station: Station, second: u8) -> Option<i64> {
+    let utc = get_utc(rdt, station, second)?;
+    Some(
+        days_from_civil(utc.year, utc.month, utc.day) as i64 * 86_400
+            + utc.hour as i64 * 3600
+            + utc.minute as i64 * 60
+            + utc.second as i64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rdt_at;
+
+    #[test]
+    fn test_get_utc_dcf77_winter() {
+        // Monday 2024-01-01 00:30 CET
+        let rdt = rdt_at(7, (24, 1, 1), (0, 30), false);
+        assert_eq!(
+            get_utc(&rdt, Station::Dcf77, 15),
+            Some(UtcDateTime {
+                year: 2023,
+                month: 12,
+                day: 31,
+                hour: 23,
+                minute: 30,
+                second: 15
+            })
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 15),
+            Some(1_704_065_415)
+        );
+    }
+    #[test]
+    fn test_get_utc_stations() {
+        // Thursday 2024-07-04 12:00
+        let rdt = rdt_at(7, (24, 7, 4), (12, 0), true);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 0),
+            Some(1_720_087_200)
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Msf, 0),
+            Some(1_720_090_800)
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Wwvb, 0),
+            Some(1_720_094_400)
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Jjy, 0),
+            Some(1_720_062_000)
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Bpc, 0),
+            Some(1_720_065_600)
+        );
+    }
+    #[test]
+    fn test_get_utc_century() {
+        // Saturday 2020-01-25 12:00 CET, 1920-01-25 was a Sunday
+        let rdt = rdt_at(7, (20, 1, 25), (12, 0), false);
+        assert_eq!(
+            get_utc(&rdt, Station::Dcf77, 0).map(|utc| utc.year),
+            Some(2020)
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 0),
+            Some(1_579_950_000)
+        );
+    }
+    #[test]
+    fn test_get_utc_century_sunday0() {
+        // Saturday 2020-01-25 12:00 UTC
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.set_year(Some(20), true, false);
+        rdt.set_month(Some(1), true, false);
+        rdt.set_weekday(Some(6), true, false);
+        rdt.set_day(Some(25), true, false);
+        rdt.set_hour(Some(12), true, false);
+        rdt.set_minute(Some(0), true, false);
+        rdt.set_dst(Some(false), Some(false), false);
+        rdt.set_leap_second(Some(false), 60);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Wwvb, 0),
+            Some(1_579_953_600)
+        );
+    }
+    #[test]
+    fn test_get_utc_dst_transition() {
+        // Sunday 2024-10-27 02:59 CEST, change to CET announced
+        let mut rdt = rdt_at(7, (24, 10, 27), (2, 59), true);
+        rdt.set_dst(Some(true), Some(true), false);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 0),
+            Some(1_729_990_740)
+        );
+        // 02:00 CET, hour shifted by add_minute() but DST flags not updated yet
+        assert!(rdt.add_minute());
+        assert_eq!(rdt.get_hour(), Some(2));
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 0),
+            Some(1_729_990_800)
+        );
+        // and after processing the change
+        rdt.set_dst(Some(false), Some(false), false);
+        assert_eq!(rdt.get_dst(), Some(crate::DST_PROCESSED));
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 0),
+            Some(1_729_990_800)
+        );
+    }
+    #[test]
+    fn test_get_utc_leap_second() {
+        // Sunday 2017-01-01 00:59 CET, leap second announced
+        let mut rdt = rdt_at(7, (17, 1, 1), (0, 59), false);
+        rdt.set_leap_second(Some(true), 60);
+        assert_eq!(
+            get_utc(&rdt, Station::Dcf77, 60),
+            Some(UtcDateTime {
+                year: 2016,
+                month: 12,
+                day: 31,
+                hour: 23,
+                minute: 59,
+                second: 60
+            })
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 60),
+            Some(1_483_228_800)
+        );
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 59),
+            Some(1_483_228_799)
+        );
+    }
+    #[test]
+    fn bad_get_utc_negative_leap_second() {
+        let mut rdt = rdt_at(7, (17, 1, 1), (0, 59), false);
+        rdt.set_signed_leap_second(Some(true), Some(true), 60);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED | LEAP_NEGATIVE));
+        assert_eq!(get_utc(&rdt, Station::Dcf77, 59), None);
+        assert_eq!(get_utc(&rdt, Station::Dcf77, 60), None);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Dcf77, 58),
+            Some(1_483_228_798)
+        );
+    }
+    #[test]
+    fn test_get_minute_length() {
+        assert_eq!(get_minute_length(LEAP_ANNOUNCED, 59), 60);
+        assert_eq!(get_minute_length(LEAP_ANNOUNCED, 0), 61);
+        assert_eq!(get_minute_length(LEAP_ANNOUNCED | LEAP_NEGATIVE, 0), 59);
+        assert_eq!(get_minute_length(LEAP_PROCESSED | LEAP_NEGATIVE, 0), 59);
+        assert_eq!(get_minute_length(LEAP_PROCESSED | LEAP_MISSING, 0), 60);
+    }
+    #[test]
+    fn bad_get_utc_leap_second() {
+        let rdt = rdt_at(7, (17, 1, 1), (0, 59), false);
+        assert_eq!(get_utc(&rdt, Station::Dcf77, 60), None);
+        assert_eq!(get_utc(&rdt, Station::Dcf77, 61), None);
+    }
+    #[test]
+    fn test_is_summer_at() {
+        let utc = |month, day, hour, minute| UtcDateTime {
+            year: 2024,
+            month,
+            day,
+            hour,
+            minute,
+            second: 0,
+        };
+        // Europe: Sunday 2024-03-31 and Sunday 2024-10-27, at 01:00 UTC
+        assert!(!is_summer_at(Station::Dcf77, &utc(3, 31, 0, 59)));
+        assert!(is_summer_at(Station::Dcf77, &utc(3, 31, 1, 0)));
+        assert!(is_summer_at(Station::Msf, &utc(10, 27, 0, 59)));
+        assert!(!is_summer_at(Station::Tdf, &utc(10, 27, 1, 0)));
+        // USA: Sunday 2024-03-10 and Sunday 2024-11-03, at the end of the day
+        assert!(!is_summer_at(Station::Wwvb, &utc(3, 10, 23, 59)));
+        assert!(is_summer_at(Station::Wwvb, &utc(3, 11, 0, 0)));
+        assert!(is_summer_at(Station::Wwvb, &utc(11, 3, 23, 59)));
+        assert!(!is_summer_at(Station::Wwvb, &utc(11, 4, 0, 0)));
+        assert!(!is_summer_at(Station::Jjy, &utc(7, 4, 12, 0)));
+    }
+    #[test]
+    fn bad_get_utc_invalid() {
+        // Monday 2024-01-01 00:30, DST and leap second unknown
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_year(Some(24), true, false);
+        rdt.set_month(Some(1), true, false);
+        rdt.set_weekday(Some(1), true, false);
+        rdt.set_day(Some(1), true, false);
+        rdt.set_hour(Some(0), true, false);
+        rdt.set_minute(Some(30), true, false);
+        assert_eq!(get_utc(&rdt, Station::Dcf77, 0), None);
+        assert_eq!(get_unix_timestamp(&rdt, Station::Wwvb, 0), None);
+        // the leap second state is not needed:
+        rdt.set_dst(Some(false), Some(false), false);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Wwvb, 0),
+            Some(1_704_069_000)
+        );
+    }
+    #[test]
+    fn test_get_utc_decoded_msf() {
+        // Saturday 2024-06-15 13:47 BST
+        let sent = rdt_at(0, (24, 6, 15), (13, 47), true);
+        let (bit_buffer, minute_length) = crate::msf::encode_time(&sent).unwrap();
+        let mut rdt = RadioDateTimeUtils::new(0);
+        crate::msf::decode_time(&mut rdt, &bit_buffer, minute_length, false);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Msf, 0),
+            Some(1_718_455_620)
+        );
+    }
+    #[test]
+    fn test_get_utc_decoded_bpc() {
+        // Thursday 2024-02-29 15:35 CST
+        let frames = crate::bpc::encode_time(&rdt_at(7, (24, 2, 29), (15, 35), false)).unwrap();
+        let mut rdt = RadioDateTimeUtils::new(7);
+        crate::bpc::decode_time(&mut rdt, &frames, false);
+        assert_eq!(
+            get_unix_timestamp(&rdt, Station::Bpc, 0),
+            Some(1_709_192_100)
+        );
+    }
+}
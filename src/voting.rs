@@ -0,0 +1,262 @@
+//! Majority voting over the last few decoded minutes.
+//!
+//! With weak reception, single bits flip and the decoded values of one minute may be
+//! wrong. `MinuteVoter` keeps the last `N` decoded minutes, advances them along with
+//! the current one, and votes per field, so a single corrupted minute does not
+//! propagate into the consensus.
+
+use crate::{RadioDateTimeUtils, BIT_BUFFER_SIZE};
+
+/// Consensus date/time over the stored minutes.
+#[derive(Clone, Copy)]
+pub struct Consensus {
+    /// date/time with each field set to the value with the most votes
+    pub rdt: RadioDateTimeUtils,
+    /// percentage of stored minutes agreeing with the consensus, the lowest over all fields
+    pub confidence: u8,
+    /// number of stored minutes which took part in the vote
+    pub minutes: u8,
+}
+
+/// Keeps the last `N` decoded minutes to vote on their fields.
+#[derive(Clone, Copy)]
+pub struct MinuteVoter<const N: usize> {
+    minutes: [Option<RadioDateTimeUtils>; N],
+    next: usize,
+}
+
+impl<const N: usize> Default for MinuteVoter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> MinuteVoter<N> {
+    /// Initialize a new, empty, MinuteVoter instance.
+    pub fn new() -> Self {
+        Self {
+            minutes: [None; N],
+            next: 0,
+        }
+    }
+
+    /// Remove all stored minutes.
+    pub fn clear(&mut self) {
+        self.minutes = [None; N];
+        self.next = 0;
+    }
+
+    /// Returns the number of stored minutes.
+    pub fn len(&self) -> usize {
+        self.minutes.iter().filter(|m| m.is_some()).count()
+    }
+
+    /// Returns if no minutes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Store a newly decoded minute, replacing the oldest one if the voter is full.
+    ///
+    /// All previously stored minutes are advanced using `add_minute()` first, minutes
+    /// which cannot be advanced because they are not valid are dropped.
+    ///
+    /// # Arguments
+    /// * `rdt` - the date/time after decoding the current minute
+    pub fn push(&mut self, rdt: &RadioDateTimeUtils) {
+        if N == 0 {
+            return;
+        }
+        for minute in &mut self.minutes {
+            if let Some(m) = minute {
+                if !m.add_minute() {
+                    *minute = None;
+                }
+            }
+        }
+        self.minutes[self.next] = Some(*rdt);
+        self.next = (self.next + 1) % N;
+    }
+
+    /// Returns the consensus over the stored minutes, or None if no minutes are stored.
+    ///
+    /// Each field is set to the value with the most votes, ties are won by the most
+    /// recent minute. Fields without any votes are None and yield a confidence of 0.
+    /// The jump values of the consensus are cleared.
+    pub fn get_consensus(&self) -> Option<Consensus> {
+        // Newest minute first, so it wins ties:
+        let mut order = [0; N];
+        let mut count = 0;
+        for age in 0..N {
+            let idx = (self.next + N - 1 - age) % N;
+            if self.minutes[idx].is_some() {
+                order[count] = idx;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        let minutes = &self.minutes;
+        let mut confidence = 100;
+        let mut vote = |get: fn(&RadioDateTimeUtils) -> Option<u8>| {
+            let (value, votes) = get_winner(order[..count].iter().map(|&idx| {
+                // minutes in `order` are all Some:
+                minutes[idx].as_ref().and_then(get)
+            }));
+            confidence = confidence.min((votes * 100 / count) as u8);
+            value
+        };
+        let mut rdt = self.minutes[order[0]].unwrap();
+        rdt.year = vote(|m| m.year);
+        rdt.month = vote(|m| m.month);
+        rdt.day = vote(|m| m.day);
+        rdt.weekday = vote(|m| m.weekday);
+        rdt.hour = vote(|m| m.hour);
+        rdt.minute = vote(|m| m.minute);
+        rdt.dst = vote(|m| m.dst);
+        rdt.leap_second = vote(|m| m.leap_second);
+        rdt.clear_jumps();
+        Some(Consensus {
+            rdt,
+            confidence,
+            minutes: count as u8,
+        })
+    }
+}
+
+/// Returns the value with the most votes and its number of votes. The first value wins
+/// in case of a tie, None values do not vote.
+///
+/// # Arguments
+/// * `values` - the values to vote on
+fn get_winner(values: impl Iterator<Item = Option<u8>> + Clone) -> (Option<u8>, usize) {
+    let mut winner = (None, 0);
+    for value in values.clone().flatten() {
+        let votes = values.clone().filter(|v| *v == Some(value)).count();
+        if votes > winner.1 {
+            winner = (Some(value), votes);
+        }
+    }
+    winner
+}
+
+/// Returns the per-bit majority of the given bit buffers, for bits which are expected
+/// to be the same in each of them, e.g. repeated transmissions of the same frame.
+///
+/// A bit is None if it has no votes or in case of a tie.
+///
+/// # Arguments
+/// * `buffers` - the bit buffers to vote on
+pub fn vote_bits(buffers: &[[Option<bool>; BIT_BUFFER_SIZE]]) -> [Option<bool>; BIT_BUFFER_SIZE] {
+    let mut result = [None; BIT_BUFFER_SIZE];
+    for (idx, bit) in result.iter_mut().enumerate() {
+        let ones = buffers.iter().filter(|b| b[idx] == Some(true)).count();
+        let zeros = buffers.iter().filter(|b| b[idx] == Some(false)).count();
+        *bit = match ones.cmp(&zeros) {
+            core::cmp::Ordering::Greater => Some(true),
+            core::cmp::Ordering::Less => Some(false),
+            core::cmp::Ordering::Equal => None,
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rdt_at;
+    use crate::{DST_SUMMER, LEAP_ANNOUNCED};
+
+    #[test]
+    fn test_consensus_empty() {
+        let voter = MinuteVoter::<5>::new();
+        assert!(voter.is_empty());
+        assert!(voter.get_consensus().is_none());
+    }
+    #[test]
+    fn test_consensus_agreeing() {
+        let mut voter = MinuteVoter::<5>::new();
+        for minute in 10..=16 {
+            voter.push(&rdt_at(7, (24, 6, 16), (12, minute), true));
+        }
+        assert_eq!(voter.len(), 5);
+        let consensus = voter.get_consensus().unwrap();
+        assert_eq!(consensus.confidence, 100);
+        assert_eq!(consensus.minutes, 5);
+        assert_eq!(consensus.rdt.get_minute(), Some(16));
+        assert_eq!(consensus.rdt.get_hour(), Some(12));
+        assert_eq!(consensus.rdt.get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn test_consensus_corrupted_minute() {
+        let mut voter = MinuteVoter::<5>::new();
+        for minute in 10..=13 {
+            voter.push(&rdt_at(7, (24, 6, 16), (12, minute), true));
+        }
+        // bit flip in the hour and the day:
+        let mut bad = rdt_at(7, (24, 6, 17), (13, 14), true);
+        bad.set_leap_second(Some(true), 60);
+        voter.push(&bad);
+        let consensus = voter.get_consensus().unwrap();
+        assert_eq!(consensus.rdt.get_hour(), Some(12));
+        assert_eq!(consensus.rdt.get_day(), Some(16));
+        assert_eq!(consensus.rdt.get_minute(), Some(14));
+        assert_eq!(consensus.rdt.get_leap_second(), Some(0));
+        assert_eq!(consensus.confidence, 80);
+        assert!(!consensus.rdt.get_jump_hour());
+        // the corrupted minute is outvoted until it is replaced:
+        for minute in 15..=19 {
+            voter.push(&rdt_at(7, (24, 6, 16), (12, minute), true));
+        }
+        assert_eq!(voter.get_consensus().unwrap().confidence, 100);
+    }
+    #[test]
+    fn test_consensus_tie_newest_wins() {
+        let mut voter = MinuteVoter::<2>::new();
+        voter.push(&rdt_at(7, (24, 6, 16), (12, 10), true));
+        let mut newer = rdt_at(7, (24, 6, 16), (12, 11), true);
+        newer.set_leap_second(Some(true), 60);
+        voter.push(&newer);
+        let consensus = voter.get_consensus().unwrap();
+        assert_eq!(consensus.rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        assert_eq!(consensus.confidence, 50);
+    }
+    #[test]
+    fn test_consensus_invalid_minutes() {
+        let mut voter = MinuteVoter::<3>::new();
+        let mut rdt = RadioDateTimeUtils::new(7);
+        rdt.set_minute(Some(10), true, false);
+        voter.push(&rdt);
+        let consensus = voter.get_consensus().unwrap();
+        assert_eq!(consensus.rdt.get_minute(), Some(10));
+        assert_eq!(consensus.rdt.get_hour(), None);
+        assert_eq!(consensus.confidence, 0);
+        // cannot be advanced, so it is dropped:
+        voter.push(&rdt_at(7, (24, 6, 16), (12, 11), true));
+        assert_eq!(voter.len(), 1);
+        assert_eq!(voter.get_consensus().unwrap().confidence, 100);
+        voter.clear();
+        assert!(voter.is_empty());
+    }
+
+    #[test]
+    fn test_vote_bits() {
+        let mut buffers = [[Some(false); BIT_BUFFER_SIZE]; 3];
+        buffers[0][5] = Some(true);
+        buffers[1][5] = Some(true);
+        buffers[2][7] = Some(true);
+        buffers[0][8] = Some(true);
+        buffers[1][8] = None;
+        buffers[2][8] = Some(false);
+        for buffer in &mut buffers {
+            buffer[9] = None;
+        }
+        let result = vote_bits(&buffers);
+        assert_eq!(result[0], Some(false));
+        assert_eq!(result[5], Some(true));
+        assert_eq!(result[7], Some(false));
+        assert_eq!(result[8], None);
+        assert_eq!(result[9], None);
+    }
+}
@@ -0,0 +1,432 @@
+//! Decoder for the 100 Hz time code of WWV (Fort Collins, Colorado, USA) and WWVH (Kauai,
+//! Hawaii, USA), transmitted on shortwave.
+//!
+//! Like WWVB, WWV and WWVH transmit UTC with one pulse per second, the time code of a
+//! minute describes the minute it is transmitted in. The pulses are 170 ms of 100 Hz tone
+//! for a 0 bit, 470 ms for a 1 bit, and 770 ms for a position marker, with no pulse at
+//! second 0. All BCD values are transmitted least significant bit first:
+//!
+//! * 2: DST at 00:00 UTC today, 55: DST at 24:00 UTC today
+//! * 3: leap second warning
+//! * 4 through 7 and 51 through 54: year
+//! * 10 through 17: minute, 20 through 26: hour, 30 through 41: day of the year
+//! * 50: sign of DUT1, set if positive, 56 through 58: magnitude of DUT1
+
+use crate::pulse::{Pulse, PulseProfile};
+use crate::radio_datetime_helpers::{
+    add_minute_day_of_year, get_bcd_value_u16, get_day_of_year, is_leap_year,
+    is_leap_year_21st_century, set_bcd_value_u16, sub_minute_day_of_year,
+};
+use crate::utc::get_minute_length;
+use crate::wwvb::{get_date, get_dst, get_dst_bits, get_leap_announce};
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, DST_SUMMER, LEAP_ANNOUNCED, LEAP_NEGATIVE,
+    LEAP_PROCESSED,
+};
+
+/// Positions of the position markers, the end-of-minute marker P0 at second 59 included.
+pub const MARKERS: [usize; 6] = [9, 19, 29, 39, 49, 59];
+
+/// Per-field outcome of decoding one WWV or WWVH minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub dst: FieldStatus,
+    pub leap_second: FieldStatus,
+    /// DUT1 in units of 0.1 second, None if absent
+    pub dut1: Option<i8>,
+}
+
+/// Returns the bits for the given pulse widths, with the position markers being None.
+///
+/// Also returns if all position markers are at their expected positions.
+///
+/// # Arguments
+/// * `widths` - the widths of the pulses in microseconds, indexed by second, None if
+///   there was no pulse
+pub fn get_bit_buffer(
+    widths: &[Option<u32>; BIT_BUFFER_SIZE],
+) -> ([Option<bool>; BIT_BUFFER_SIZE], bool) {
+    let mut bit_buffer = [None; BIT_BUFFER_SIZE];
+    let mut markers_ok = true;
+    for (idx, (bit, width)) in bit_buffer.iter_mut().zip(widths).enumerate() {
+        let pulse = width.map(|w| PulseProfile::WWV.classify(w));
+        *bit = match pulse {
+            Some(Pulse::Zero) => Some(false),
+            Some(Pulse::One) => Some(true),
+            _ => None,
+        };
+        if MARKERS.contains(&idx) != (pulse == Some(Pulse::Marker)) {
+            markers_ok = false;
+        }
+    }
+    (bit_buffer, markers_ok)
+}
+
+/// Decode the pulse widths of one WWV or WWVH minute into the given date/time instance.
+///
+/// Like with WWVB, the date/time is set to the minute starting at the end of the pulses,
+/// the day of the week is calculated from the date with Sunday being 0, and DST changes
+/// are applied at 00:00 UTC. Leap years are assumed to be in the 21st century.
+///
+/// The date/time is first advanced by one minute using `add_minute_utc()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(0)`
+/// * `widths` - the widths of the pulses in microseconds, indexed by second, None if
+///   there was no pulse
+/// * `minute_length` - the length of the decoded minute in seconds, 59 through 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    widths: &[Option<u32>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    // WWV transmits UTC, so the local hour change of add_minute() does not apply:
+    let added_minute = rdt.add_minute_utc();
+    let check_jump = check_jump && added_minute;
+    let (mut bit_buffer, markers_ok) = get_bit_buffer(widths);
+    let frame_ok = (59..=61).contains(&minute_length) && markers_ok;
+    // Ignore the position markers, some of them separate the digits of a field:
+    for idx in MARKERS {
+        bit_buffer[idx] = Some(false);
+    }
+    let bit_buffer = &bit_buffer;
+
+    let mut minute = get_bcd_value_u16(bit_buffer, &[(10, 13), (15, 17)])
+        .filter(|_| frame_ok)
+        .map(|m| m as u8);
+    let mut hour = get_bcd_value_u16(bit_buffer, &[(20, 23), (25, 26)])
+        .filter(|_| frame_ok)
+        .map(|h| h as u8);
+    let mut day_of_year =
+        get_bcd_value_u16(bit_buffer, &[(30, 33), (35, 38), (40, 41)]).filter(|_| frame_ok);
+    let mut year = get_bcd_value_u16(bit_buffer, &[(4, 7), (51, 54)])
+        .filter(|_| frame_ok)
+        .map(|y| y as u8);
+    let mut leap_year = year.and_then(is_leap_year_21st_century);
+    add_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+    let (month, day, weekday) = get_date(year, leap_year, day_of_year);
+
+    rdt.set_minute(minute, true, check_jump);
+    rdt.set_hour(hour, true, check_jump);
+    rdt.set_year(year, true, check_jump);
+    rdt.set_month(month, true, check_jump);
+    rdt.set_weekday(weekday, true, check_jump);
+    // day must be set *after* year, month, and weekday
+    rdt.set_day(day, true, check_jump);
+
+    let (dst, dst_announce) = get_dst(
+        bit_buffer[55].filter(|_| frame_ok),
+        bit_buffer[2],
+        hour,
+        minute,
+    );
+    rdt.set_dst(dst, dst_announce, check_jump);
+    let leap_announce = get_leap_announce(bit_buffer[3], leap_year, day_of_year, hour);
+    // Like WWVB, WWV does not transmit the sign of a leap second:
+    rdt.set_leap_second(leap_announce, minute_length);
+    rdt.bump_minutes_running();
+
+    let mut year_bits = [None; 8];
+    year_bits[..4].copy_from_slice(&bit_buffer[4..=7]);
+    year_bits[4..].copy_from_slice(&bit_buffer[51..=54]);
+    DecodeReport {
+        minute: FieldStatus::new(&bit_buffer[10..=17], Some(true), minute, rdt.get_minute()),
+        hour: FieldStatus::new(&bit_buffer[20..=26], Some(true), hour, rdt.get_hour()),
+        day: FieldStatus::new(&bit_buffer[30..=41], Some(true), day, rdt.get_day()),
+        weekday: FieldStatus::new(&bit_buffer[30..=41], Some(true), weekday, rdt.get_weekday()),
+        month: FieldStatus::new(&bit_buffer[30..=41], Some(true), month, rdt.get_month()),
+        year: FieldStatus::new(&year_bits, Some(true), year, rdt.get_year()),
+        dst: FieldStatus::new(
+            &[bit_buffer[2], bit_buffer[55]],
+            Some(true),
+            dst.map(|d| d as u8),
+            rdt.get_dst().map(|d| (d & DST_SUMMER != 0) as u8),
+        ),
+        leap_second: FieldStatus::new(
+            &bit_buffer[3..=3],
+            Some(true),
+            Some(minute_length).filter(|m| (59..=61).contains(m)),
+            Some(minute_length),
+        ),
+        dut1: get_dut1(bit_buffer),
+    }
+}
+
+/// Encode the given date/time into the nominal pulse widths of one WWV or WWVH minute,
+/// the inverse of `decode_time()`.
+///
+/// The transmitted minute is the one before `rdt`. An announced DST change is encoded
+/// as taking effect at the end of the transmitted day. DUT1 is encoded as +0.0 s, or
+/// as +0.5 s if a negative leap second is announced. Seconds without a pulse are None.
+///
+/// Returns the pulse widths in microseconds and the length of the minute in seconds,
+/// or None if the date/time is not valid.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the pulses
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<u32>; BIT_BUFFER_SIZE], u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let mut minute = rdt.get_minute().unwrap();
+    let mut hour = rdt.get_hour().unwrap();
+    let mut year = rdt.get_year().unwrap();
+    let mut leap_year = is_leap_year(rdt.get_full_year()?);
+    let mut day_of_year =
+        get_day_of_year(rdt.get_month().unwrap(), rdt.get_day().unwrap(), leap_year)?;
+    let dst_bits = get_dst_bits(rdt.get_dst().unwrap(), hour, minute);
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    let leap_warning =
+        leap_second & LEAP_ANNOUNCED != 0 || minute == 0 && leap_second & LEAP_PROCESSED != 0;
+    let minute_length = get_minute_length(leap_second, minute);
+    sub_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+
+    let mut bit_buffer = [Some(false); BIT_BUFFER_SIZE];
+    bit_buffer[2] = Some(dst_bits.1);
+    bit_buffer[3] = Some(leap_warning);
+    set_bcd_value_u16(&mut bit_buffer, &[(4, 7), (51, 54)], year as u16);
+    set_bcd_value_u16(&mut bit_buffer, &[(10, 13), (15, 17)], minute as u16);
+    set_bcd_value_u16(&mut bit_buffer, &[(20, 23), (25, 26)], hour as u16);
+    set_bcd_value_u16(
+        &mut bit_buffer,
+        &[(30, 33), (35, 38), (40, 41)],
+        day_of_year,
+    );
+    bit_buffer[50] = Some(true);
+    bit_buffer[55] = Some(dst_bits.0);
+    if leap_second & LEAP_NEGATIVE != 0 {
+        // DUT1 must be positive for a negative leap second, use +0.5 s:
+        bit_buffer[56] = Some(true);
+        bit_buffer[58] = Some(true);
+    }
+
+    let mut widths = [None; BIT_BUFFER_SIZE];
+    for (idx, width) in widths
+        .iter_mut()
+        .enumerate()
+        .take(minute_length as usize)
+        .skip(1)
+    {
+        *width = Some(if MARKERS.contains(&idx) {
+            PulseProfile::WWV.marker_width.unwrap()
+        } else if bit_buffer[idx] == Some(true) {
+            PulseProfile::WWV.one_width
+        } else {
+            PulseProfile::WWV.zero_width
+        });
+    }
+    Some((widths, minute_length))
+}
+
+/// Return DUT1 in units of 0.1 second from bits 50 and 56 through 58, or None if the
+/// input is invalid.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits
+fn get_dut1(bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE]) -> Option<i8> {
+    let mut magnitude = 0;
+    for (idx, bit) in bit_buffer[56..=58].iter().enumerate() {
+        magnitude |= ((*bit)? as i8) << idx;
+    }
+    Some(if bit_buffer[50]? {
+        magnitude
+    } else {
+        -magnitude
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::rdt_at;
+    use crate::{DST_ANNOUNCED, DST_PROCESSED};
+
+    // Thursday 2024-02-29 (day 60) 12:34 UTC, DUT1 = -0.3 s
+    fn frame() -> [Option<u32>; BIT_BUFFER_SIZE] {
+        let (mut widths, _) = encode_time(&rdt_at(0, (24, 2, 29), (12, 35), false)).unwrap();
+        widths[50] = Some(PulseProfile::WWV.zero_width);
+        widths[56] = Some(PulseProfile::WWV.one_width);
+        widths[57] = Some(PulseProfile::WWV.one_width);
+        widths
+    }
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &frame(), 60, false);
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_month(), Some(2));
+        assert_eq!(rdt.get_day(), Some(29));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(report.year, FieldStatus::Ok);
+        assert_eq!(report.dst, FieldStatus::Ok);
+        assert_eq!(report.dut1, Some(-3));
+    }
+    #[test]
+    fn test_decode_time_bad_marker() {
+        let mut widths = frame();
+        widths[29] = Some(170_000);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(&mut rdt, &widths, 60, false);
+        assert_eq!(rdt.get_minute(), None);
+        assert_eq!(rdt.get_dst(), None);
+    }
+    #[test]
+    fn test_decode_time_invalid_pulse() {
+        let mut widths = frame();
+        widths[36] = Some(320_000);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &widths, 60, false);
+        assert_eq!(rdt.get_day(), None);
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert_eq!(report.day, FieldStatus::Missing);
+    }
+    #[test]
+    fn test_get_bit_buffer() {
+        let (bits, markers_ok) = get_bit_buffer(&frame());
+        assert!(markers_ok);
+        assert_eq!(bits[0], None);
+        assert_eq!(bits[9], None);
+        assert_eq!(bits[12], Some(true));
+        assert_eq!(bits[13], Some(false));
+    }
+    #[test]
+    fn test_encode_time_regular() {
+        let (widths, minute_length) =
+            encode_time(&rdt_at(0, (24, 2, 29), (12, 35), false)).unwrap();
+        assert_eq!(minute_length, 60);
+        assert_eq!(widths[0], None);
+        assert_eq!(widths[59], PulseProfile::WWV.marker_width);
+        assert_eq!(widths[60..], [None, None]);
+        let (bits, markers_ok) = get_bit_buffer(&widths);
+        assert!(markers_ok);
+        assert_eq!(get_bcd_value_u16(&bits, &[(4, 7), (51, 54)]), Some(24));
+        assert_eq!(get_bcd_value_u16(&bits, &[(10, 13), (15, 17)]), Some(34));
+        assert_eq!(get_bcd_value_u16(&bits, &[(20, 23), (25, 26)]), Some(12));
+        assert_eq!(
+            get_bcd_value_u16(&bits, &[(30, 33), (35, 38), (40, 41)]),
+            Some(60)
+        );
+        // DUT1 is encoded as +0.0 s:
+        assert_eq!(get_dut1(&bits), Some(0));
+    }
+    #[test]
+    fn test_encode_time_century() {
+        let mut rdt = rdt_at(0, (0, 3, 1), (12, 0), false);
+        rdt.set_pivot_year(Some(2050));
+        let (widths, _) = encode_time(&rdt).unwrap();
+        let (bits, _) = get_bit_buffer(&widths);
+        // 2100 is not a leap year:
+        assert_eq!(
+            get_bcd_value_u16(&bits, &[(30, 33), (35, 38), (40, 41)]),
+            Some(60)
+        );
+    }
+    #[test]
+    fn continue_decode_time_dst_begins() {
+        // Sunday 2024-03-10, DST begins at 24:00 UTC
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut expected = rdt_at(0, (24, 3, 10), (22, 1), false);
+        expected.set_dst(Some(false), Some(true), false);
+        assert_eq!(expected.get_dst(), Some(DST_ANNOUNCED));
+        for hour in 22..=23 {
+            for minute in 0..=59 {
+                expected.set_hour(Some(hour), true, false);
+                expected.set_minute(Some(minute), true, false);
+                let (widths, minute_length) = encode_time(&expected).unwrap();
+                assert_eq!(
+                    (widths[2], widths[55]),
+                    (
+                        Some(PulseProfile::WWV.zero_width),
+                        Some(PulseProfile::WWV.one_width)
+                    )
+                );
+                decode_time(&mut rdt, &widths, minute_length, hour == 23);
+            }
+        }
+        assert_eq!(rdt.get_dst(), Some(DST_ANNOUNCED));
+        expected.set_weekday(Some(1), true, false);
+        expected.set_day(Some(11), true, false);
+        expected.set_hour(Some(0), true, false);
+        expected.set_minute(Some(0), true, false);
+        expected.set_dst(Some(true), Some(false), false);
+        assert_eq!(expected.get_dst(), Some(DST_SUMMER | DST_PROCESSED));
+        let (widths, minute_length) = encode_time(&expected).unwrap();
+        decode_time(&mut rdt, &widths, minute_length, true);
+        assert_eq!(rdt.get_day(), Some(11));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert!(!rdt.get_jump_minute() && !rdt.get_jump_hour() && !rdt.get_jump_day());
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_PROCESSED));
+    }
+    #[test]
+    fn continue_decode_time_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut expected = rdt_at(0, (24, 6, 30), (23, 1), false);
+        expected.set_leap_second(Some(true), 60);
+        assert_eq!(expected.get_leap_second(), Some(LEAP_ANNOUNCED));
+        for minute in 0..60 {
+            expected.set_minute(Some(minute), true, false);
+            let (widths, minute_length) = encode_time(&expected).unwrap();
+            decode_time(&mut rdt, &widths, minute_length, minute != 0);
+        }
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        expected.set_month(Some(7), true, false);
+        expected.set_weekday(Some(1), true, false);
+        expected.set_day(Some(1), true, false);
+        expected.set_hour(Some(0), true, false);
+        expected.set_minute(Some(0), true, false);
+        let (widths, minute_length) = encode_time(&expected).unwrap();
+        assert_eq!(minute_length, 61);
+        decode_time(&mut rdt, &widths, minute_length, true);
+        assert_eq!(rdt.get_month(), Some(7));
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+    }
+    #[test]
+    fn continue_decode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut expected = rdt_at(0, (24, 6, 30), (23, 1), false);
+        expected.set_signed_leap_second(Some(true), Some(true), 60);
+        for minute in 0..60 {
+            expected.set_minute(Some(minute), true, false);
+            let (widths, minute_length) = encode_time(&expected).unwrap();
+            decode_time(&mut rdt, &widths, minute_length, minute != 0);
+        }
+        // The sign is not transmitted, only the length of the last minute shows it:
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        assert!(expected.add_minute());
+        let (widths, minute_length) = encode_time(&expected).unwrap();
+        assert_eq!(minute_length, 59);
+        decode_time(&mut rdt, &widths, minute_length, true);
+        assert_eq!(rdt.get_month(), Some(7));
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(0)), None);
+    }
+}
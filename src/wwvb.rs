@@ -0,0 +1,695 @@
+//! Decoder for the legacy amplitude-modulated time code of WWVB (Fort Collins, Colorado, USA, 60 kHz).
+//!
+//! WWVB transmits UTC, the time code of a minute describes the minute it is transmitted in.
+
+use crate::radio_datetime_helpers::{
+    add_minute_day_of_year, get_bcd_value, get_bcd_value_u16, get_day_of_year, get_month_day,
+    get_weekday, is_leap_year, set_bcd_value, set_bcd_value_u16, sub_minute_day_of_year,
+};
+use crate::utc::get_minute_length;
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, DST_ANNOUNCED, DST_PROCESSED, DST_SUMMER,
+    LEAP_ANNOUNCED, LEAP_NEGATIVE, LEAP_PROCESSED,
+};
+
+/// Positions of the position markers, including the frame reference marker at second 0.
+pub const MARKERS: [usize; 7] = [0, 9, 19, 29, 39, 49, 59];
+
+/// Positions of the unused bits, which are always 0.
+const UNUSED: [usize; 11] = [4, 10, 11, 14, 20, 21, 24, 34, 35, 44, 54];
+
+/// Numeric value of Sunday, WWVB does not transmit the day of the week.
+const SUNDAY: u8 = 0;
+
+/// Per-field outcome of decoding one WWVB minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub dst: FieldStatus,
+    pub leap_second: FieldStatus,
+    /// DUT1 in units of 0.1 second, None if absent or inconsistent
+    pub dut1: Option<i8>,
+}
+
+/// Returns the DST bitmask for the two WWVB DST bits, or None if the input is invalid.
+///
+/// The mask only uses `DST_SUMMER` and `DST_ANNOUNCED`, a change being announced means
+/// that it takes place today.
+///
+/// # Arguments
+/// * `at_day_end` - bit 57, if DST is in effect at 24:00 UTC today
+/// * `at_day_start` - bit 58, if DST is in effect at 00:00 UTC today
+pub fn get_dst_mask(at_day_end: Option<bool>, at_day_start: Option<bool>) -> Option<u8> {
+    match (at_day_end?, at_day_start?) {
+        (false, false) => Some(0),
+        (true, false) => Some(DST_ANNOUNCED),
+        (true, true) => Some(DST_SUMMER),
+        (false, true) => Some(DST_SUMMER | DST_ANNOUNCED),
+    }
+}
+
+/// Returns if DST is in effect and if a DST change is announced, for the two WWVB DST
+/// bits and the UTC time at the end of the minute they were transmitted in.
+///
+/// Both DST bits stay set for the whole day, so a change is only announced during the
+/// hour before 00:00 UTC. At 00:00 UTC itself, the bits still describe the previous day.
+///
+/// # Arguments
+/// * `at_day_end` - if DST is in effect at 24:00 UTC on the transmitted day
+/// * `at_day_start` - if DST is in effect at 00:00 UTC on the transmitted day
+/// * `hour` - the UTC hour at the end of the minute
+/// * `minute` - the minute at the end of the minute
+pub(crate) fn get_dst(
+    at_day_end: Option<bool>,
+    at_day_start: Option<bool>,
+    hour: Option<u8>,
+    minute: Option<u8>,
+) -> (Option<bool>, Option<bool>) {
+    let dst_mask = get_dst_mask(at_day_end, at_day_start);
+    let at_midnight = hour == Some(0) && minute == Some(0);
+    let last_hour = hour == Some(23);
+    (
+        dst_mask.map(|mask| {
+            if at_midnight {
+                at_day_end == Some(true)
+            } else {
+                mask & DST_SUMMER != 0
+            }
+        }),
+        dst_mask.map(|mask| mask & DST_ANNOUNCED != 0 && last_hour),
+    )
+}
+
+/// Returns the month, the day of the month, and the day of the week (Sunday being 0)
+/// for the given day of the year.
+///
+/// # Arguments
+/// * `year` - year, truncated to two digits
+/// * `leap_year` - if the year is a leap year
+/// * `day_of_year` - day of the year, 1 through 365 or 366
+pub(crate) fn get_date(
+    year: Option<u8>,
+    leap_year: Option<bool>,
+    day_of_year: Option<u16>,
+) -> (Option<u8>, Option<u8>, Option<u8>) {
+    let month_day = day_of_year
+        .zip(leap_year)
+        .and_then(|(d, l)| get_month_day(d, l));
+    let weekday = year
+        .zip(month_day)
+        .and_then(|(y, (m, d))| get_weekday(y, m, d, SUNDAY));
+    (month_day.map(|md| md.0), month_day.map(|md| md.1), weekday)
+}
+
+/// Returns if a leap second is announced, for the leap second warning bit and the UTC
+/// date/time at the end of the minute it was transmitted in.
+///
+/// The leap second warning stays set for the whole month, so it only counts during the
+/// hour before the change, on the last day of the month.
+///
+/// # Arguments
+/// * `warning` - the leap second warning bit
+/// * `leap_year` - if the year is a leap year
+/// * `day_of_year` - day of the year, 1 through 365 or 366
+/// * `hour` - the UTC hour at the end of the minute
+pub(crate) fn get_leap_announce(
+    warning: Option<bool>,
+    leap_year: Option<bool>,
+    day_of_year: Option<u16>,
+    hour: Option<u8>,
+) -> Option<bool> {
+    let last_day = day_of_year
+        .zip(leap_year)
+        .map(|(d, l)| get_month_day(d + 1, l).is_none_or(|md| md.1 == 1));
+    warning.map(|ls| ls && hour == Some(23) && last_day == Some(true))
+}
+
+/// Returns the two WWVB DST bits for the given DST bitmask, the inverse of `get_dst()`.
+///
+/// An announced DST change is encoded as taking effect at the end of the transmitted
+/// day, so the bits are (at 24:00 UTC, at 00:00 UTC).
+///
+/// # Arguments
+/// * `dst` - the DST bitmask at the end of the transmitted minute
+/// * `hour` - the UTC hour at the end of the transmitted minute
+/// * `minute` - the minute at the end of the transmitted minute
+pub(crate) fn get_dst_bits(dst: u8, hour: u8, minute: u8) -> (bool, bool) {
+    let summer = dst & DST_SUMMER != 0;
+    if dst & DST_ANNOUNCED != 0 {
+        (!summer, summer)
+    } else if dst & DST_PROCESSED != 0 && hour == 0 && minute == 0 {
+        (summer, !summer)
+    } else {
+        (summer, summer)
+    }
+}
+
+/// Decode the bit buffer of one WWVB minute into the given date/time instance.
+///
+/// Like with DCF77 and MSF, the date/time is set to the minute starting at the end of
+/// the bit buffer, so one minute is added to the transmitted time. The day of the week
+/// is calculated from the date, with Sunday being 0.
+///
+/// The date/time is first advanced by one minute using `add_minute_utc()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// Daylight saving time changes are applied at 00:00 UTC, when the DST bits change.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(0)`
+/// * `bit_buffer` - the bits of the minute, indexed by second. The values at the
+///   position markers are ignored.
+/// * `minute_length` - the length of the decoded minute in seconds, 59 through 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    // WWVB transmits UTC, so the local hour change of add_minute() does not apply:
+    let added_minute = rdt.add_minute_utc();
+    let check_jump = check_jump && added_minute;
+    // Ignore the position markers, some of them separate the digits of a field:
+    let mut bits = *bit_buffer;
+    for idx in MARKERS {
+        bits[idx] = Some(false);
+    }
+    let bit_buffer = &bits;
+    let frame_ok = (59..=61).contains(&minute_length)
+        && UNUSED.iter().all(|idx| bit_buffer[*idx] != Some(true));
+
+    let mut minute = get_bcd_value_u16(bit_buffer, &[(8, 5), (3, 1)])
+        .filter(|_| frame_ok)
+        .map(|m| m as u8);
+    let mut hour = get_bcd_value_u16(bit_buffer, &[(18, 15), (13, 12)])
+        .filter(|_| frame_ok)
+        .map(|h| h as u8);
+    let mut day_of_year =
+        get_bcd_value_u16(bit_buffer, &[(33, 30), (28, 25), (23, 22)]).filter(|_| frame_ok);
+    let mut year = get_bcd_value_u16(bit_buffer, &[(53, 50), (48, 45)])
+        .filter(|_| frame_ok)
+        .map(|y| y as u8);
+    let mut leap_year = bit_buffer[55];
+    add_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+    let (month, day, weekday) = get_date(year, leap_year, day_of_year);
+
+    rdt.set_minute(minute, true, check_jump);
+    rdt.set_hour(hour, true, check_jump);
+    rdt.set_year(year, true, check_jump);
+    rdt.set_month(month, true, check_jump);
+    rdt.set_weekday(weekday, true, check_jump);
+    // day must be set *after* year, month, and weekday
+    rdt.set_day(day, true, check_jump);
+
+    let (dst, dst_announce) = get_dst(
+        bit_buffer[57].filter(|_| frame_ok),
+        bit_buffer[58],
+        hour,
+        minute,
+    );
+    rdt.set_dst(dst, dst_announce, check_jump);
+    let leap_announce = get_leap_announce(bit_buffer[56], leap_year, day_of_year, hour);
+    // WWVB does not transmit the sign of a leap second:
+    rdt.set_leap_second(leap_announce, minute_length);
+    rdt.bump_minutes_running();
+
+    DecodeReport {
+        minute: FieldStatus::new(&bit_buffer[1..=8], Some(true), minute, rdt.get_minute()),
+        hour: FieldStatus::new(&bit_buffer[12..=18], Some(true), hour, rdt.get_hour()),
+        day: FieldStatus::new(&bit_buffer[22..=33], Some(true), day, rdt.get_day()),
+        weekday: FieldStatus::new(&bit_buffer[22..=33], Some(true), weekday, rdt.get_weekday()),
+        month: FieldStatus::new(&bit_buffer[22..=33], Some(true), month, rdt.get_month()),
+        year: FieldStatus::new(&bit_buffer[45..=55], Some(true), year, rdt.get_year()),
+        dst: FieldStatus::new(
+            &bit_buffer[57..=58],
+            Some(true),
+            dst.map(|d| d as u8),
+            rdt.get_dst().map(|d| (d & DST_SUMMER != 0) as u8),
+        ),
+        leap_second: FieldStatus::new(
+            &bit_buffer[56..=56],
+            Some(true),
+            Some(minute_length).filter(|m| (59..=61).contains(m)),
+            Some(minute_length),
+        ),
+        dut1: get_dut1(bit_buffer),
+    }
+}
+
+/// Encode the given date/time into the bit buffer of one WWVB minute, the inverse of
+/// `decode_time()`.
+///
+/// The transmitted minute is the one before `rdt`. An announced DST change is encoded
+/// as taking effect at the end of the transmitted day. Position markers, and unused bits
+/// at the end, are None. DUT1 is encoded as +0.0 s, or as +0.5 s if a negative leap
+/// second is announced.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<bool>; BIT_BUFFER_SIZE], u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let mut minute = rdt.get_minute().unwrap();
+    let mut hour = rdt.get_hour().unwrap();
+    let mut year = rdt.get_year().unwrap();
+    let mut leap_year = is_leap_year(rdt.get_full_year()?);
+    let mut day_of_year =
+        get_day_of_year(rdt.get_month().unwrap(), rdt.get_day().unwrap(), leap_year)?;
+    let dst = rdt.get_dst().unwrap();
+    let dst_bits = get_dst_bits(dst, hour, minute);
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    let leap_warning =
+        leap_second & LEAP_ANNOUNCED != 0 || minute == 0 && leap_second & LEAP_PROCESSED != 0;
+    let minute_length = get_minute_length(leap_second, minute);
+    sub_minute_day_of_year(
+        &mut year,
+        &mut leap_year,
+        &mut day_of_year,
+        &mut hour,
+        &mut minute,
+    );
+
+    let mut bit_buffer = [Some(false); BIT_BUFFER_SIZE];
+    for bit in &mut bit_buffer[60..] {
+        *bit = None;
+    }
+    set_bcd_value_u16(&mut bit_buffer, &[(8, 5), (3, 1)], minute as u16);
+    set_bcd_value_u16(&mut bit_buffer, &[(18, 15), (13, 12)], hour as u16);
+    set_bcd_value_u16(
+        &mut bit_buffer,
+        &[(33, 30), (28, 25), (23, 22)],
+        day_of_year,
+    );
+    bit_buffer[36] = Some(true);
+    bit_buffer[38] = Some(true);
+    if leap_second & LEAP_NEGATIVE != 0 {
+        // DUT1 must be positive for a negative leap second, use +0.5 s:
+        set_bcd_value(&mut bit_buffer, 43, 40, 5);
+    }
+    set_bcd_value_u16(&mut bit_buffer, &[(53, 50), (48, 45)], year as u16);
+    bit_buffer[55] = Some(leap_year);
+    bit_buffer[56] = Some(leap_warning);
+    bit_buffer[57] = Some(dst_bits.0);
+    bit_buffer[58] = Some(dst_bits.1);
+    for idx in MARKERS {
+        bit_buffer[idx] = None;
+    }
+    Some((bit_buffer, minute_length))
+}
+
+/// Return DUT1 in units of 0.1 second from bits 36 through 43, or None if the input is invalid.
+///
+/// # Arguments
+/// * `bit_buffer` - buffer containing the bits
+fn get_dut1(bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE]) -> Option<i8> {
+    let magnitude = get_bcd_value(bit_buffer, 43, 40)? as i8;
+    match (bit_buffer[36]?, bit_buffer[37]?, bit_buffer[38]?) {
+        (true, false, true) => Some(magnitude),
+        (false, true, false) => Some(-magnitude),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio_datetime_helpers::is_leap_year_21st_century;
+    use crate::tests::rdt_at;
+    use crate::{DST_PROCESSED, LEAP_ANNOUNCED, LEAP_PROCESSED};
+
+    /// Build a minute with the given date/time, DST bits 57/58 and leap second warning.
+    fn frame(
+        year: u8,
+        doy: u16,
+        hour: u8,
+        minute: u8,
+        dst: (bool, bool),
+        lsw: bool,
+    ) -> [Option<bool>; BIT_BUFFER_SIZE] {
+        let mut buffer = [Some(false); BIT_BUFFER_SIZE];
+        for idx in MARKERS {
+            buffer[idx] = None;
+        }
+        buffer[60] = None;
+        buffer[61] = None;
+        set_bcd_value_u16(&mut buffer, &[(8, 5), (3, 1)], minute as u16);
+        set_bcd_value_u16(&mut buffer, &[(18, 15), (13, 12)], hour as u16);
+        set_bcd_value_u16(&mut buffer, &[(33, 30), (28, 25), (23, 22)], doy);
+        // DUT1 = -0.3 s
+        buffer[37] = Some(true);
+        set_bcd_value(&mut buffer, 43, 40, 3);
+        set_bcd_value_u16(&mut buffer, &[(53, 50), (48, 45)], year as u16);
+        buffer[55] = is_leap_year_21st_century(year);
+        buffer[56] = Some(lsw);
+        buffer[57] = Some(dst.0);
+        buffer[58] = Some(dst.1);
+        buffer
+    }
+
+    #[test]
+    fn test_decode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Thursday 2024-02-29 12:34 UTC, so 12:35 at the end of the minute
+        let report = decode_time(
+            &mut rdt,
+            &frame(24, 60, 12, 34, (false, false), false),
+            60,
+            true,
+        );
+        assert_eq!(
+            report,
+            DecodeReport {
+                minute: FieldStatus::Ok,
+                hour: FieldStatus::Ok,
+                day: FieldStatus::Ok,
+                weekday: FieldStatus::Ok,
+                month: FieldStatus::Ok,
+                year: FieldStatus::Ok,
+                dst: FieldStatus::Ok,
+                leap_second: FieldStatus::Ok,
+                dut1: Some(-3),
+            }
+        );
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_day(), Some(29));
+        assert_eq!(rdt.get_month(), Some(2));
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert!(rdt.is_valid());
+    }
+    #[test]
+    fn test_decode_time_new_year() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Friday 1999-12-31 23:59 UTC
+        decode_time(
+            &mut rdt,
+            &frame(99, 365, 23, 59, (false, false), false),
+            60,
+            true,
+        );
+        assert_eq!(rdt.get_minute(), Some(0));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_day(), Some(1));
+        assert_eq!(rdt.get_month(), Some(1));
+        assert_eq!(rdt.get_year(), Some(0));
+        assert_eq!(rdt.get_weekday(), Some(6));
+    }
+    #[test]
+    fn continue_decode_time_no_jump() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(
+            &mut rdt,
+            &frame(24, 60, 12, 34, (true, true), false),
+            60,
+            true,
+        );
+        decode_time(
+            &mut rdt,
+            &frame(24, 60, 12, 35, (true, true), false),
+            60,
+            true,
+        );
+        assert_eq!(rdt.get_minute(), Some(36));
+        assert!(!rdt.get_jump_minute());
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn test_decode_time_bad_frame() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(24, 60, 12, 34, (false, false), false);
+        buffer[4] = Some(true);
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.minute, FieldStatus::Invalid);
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn test_decode_time_bad_day_of_year() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // 2023 is not a leap year
+        let report = decode_time(
+            &mut rdt,
+            &frame(23, 366, 12, 34, (false, false), false),
+            60,
+            false,
+        );
+        assert_eq!(report.day, FieldStatus::Invalid);
+        assert_eq!(report.month, FieldStatus::Invalid);
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(rdt.get_day(), None);
+    }
+    #[test]
+    fn test_decode_time_missing_minute() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(24, 60, 12, 34, (false, false), false);
+        buffer[6] = None;
+        let report = decode_time(&mut rdt, &buffer, 60, false);
+        assert_eq!(report.minute, FieldStatus::Missing);
+        assert_eq!(report.hour, FieldStatus::Invalid); // might have rolled over
+        assert_eq!(rdt.get_minute(), None);
+    }
+    #[test]
+    fn continue_decode_time_dst_begins() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Sunday 2024-03-10 is day 70, DST begins today
+        for minute in 0..=59 {
+            decode_time(
+                &mut rdt,
+                &frame(24, 70, 22, minute, (true, false), false),
+                60,
+                true,
+            );
+        }
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(24, 70, 23, minute, (true, false), false),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_dst(), Some(DST_ANNOUNCED));
+        decode_time(
+            &mut rdt,
+            &frame(24, 70, 23, 59, (true, false), false),
+            60,
+            true,
+        );
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_day(), Some(11));
+        assert!(!rdt.get_jump_minute() && !rdt.get_jump_hour() && !rdt.get_jump_day());
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_PROCESSED));
+        decode_time(
+            &mut rdt,
+            &frame(24, 71, 0, 0, (true, true), false),
+            60,
+            true,
+        );
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+        assert!(!rdt.get_jump_minute());
+    }
+    #[test]
+    fn continue_decode_time_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Saturday 2016-12-31 is day 366
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(16, 366, 23, minute, (false, false), true),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        decode_time(
+            &mut rdt,
+            &frame(16, 366, 23, 59, (false, false), true),
+            61,
+            true,
+        );
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+        assert_eq!(rdt.get_year(), Some(17));
+    }
+    #[test]
+    fn test_decode_time_leap_second_warning_early() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // warning is sent the whole month, Thursday 2016-12-01
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(16, 336, 23, minute, (false, false), true),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_leap_second(), Some(0));
+    }
+
+    #[test]
+    fn test_get_dst_mask() {
+        assert_eq!(get_dst_mask(Some(false), Some(false)), Some(0));
+        assert_eq!(get_dst_mask(Some(true), Some(false)), Some(DST_ANNOUNCED));
+        assert_eq!(get_dst_mask(Some(true), Some(true)), Some(DST_SUMMER));
+        assert_eq!(
+            get_dst_mask(Some(false), Some(true)),
+            Some(DST_SUMMER | DST_ANNOUNCED)
+        );
+        assert_eq!(get_dst_mask(None, Some(true)), None);
+    }
+
+    #[test]
+    fn test_get_dut1_positive() {
+        let mut buffer = [Some(false); BIT_BUFFER_SIZE];
+        buffer[36] = Some(true);
+        buffer[38] = Some(true);
+        set_bcd_value(&mut buffer, 43, 40, 7);
+        assert_eq!(get_dut1(&buffer), Some(7));
+    }
+    #[test]
+    fn bad_get_dut1_sign() {
+        let mut buffer = [Some(false); BIT_BUFFER_SIZE];
+        buffer[36] = Some(true);
+        buffer[37] = Some(true);
+        buffer[38] = Some(true);
+        assert_eq!(get_dut1(&buffer), None);
+    }
+
+    #[test]
+    fn test_encode_time_regular() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let mut buffer = frame(24, 60, 12, 34, (false, false), false);
+        decode_time(&mut rdt, &buffer, 60, false);
+        // DUT1 = +0.0 s
+        buffer[36] = Some(true);
+        buffer[37] = Some(false);
+        buffer[38] = Some(true);
+        buffer[42] = Some(false);
+        buffer[43] = Some(false);
+        assert_eq!(encode_time(&rdt), Some((buffer, 60)));
+    }
+    #[test]
+    fn test_encode_time_century() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        rdt.set_pivot_year(Some(2050));
+        // Monday 2100-03-01 12:00 UTC, 2100 is not a leap year
+        rdt.set_year(Some(0), true, false);
+        rdt.set_month(Some(3), true, false);
+        rdt.set_weekday(Some(1), true, false);
+        rdt.set_day(Some(1), true, false);
+        rdt.set_hour(Some(12), true, false);
+        rdt.set_minute(Some(0), true, false);
+        rdt.set_dst(Some(false), Some(false), false);
+        rdt.set_leap_second(Some(false), 60);
+        let (buffer, _) = encode_time(&rdt).unwrap();
+        assert_eq!(
+            get_bcd_value_u16(&buffer, &[(33, 30), (28, 25), (23, 22)]),
+            Some(60)
+        );
+        assert_eq!(buffer[55], Some(false));
+    }
+    #[test]
+    fn test_encode_time_round_trip() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        // Friday 1999-12-31 12:00 UTC
+        decode_time(
+            &mut rdt,
+            &frame(99, 365, 11, 59, (true, true), false),
+            60,
+            false,
+        );
+        for _ in 0..(24 * 60) {
+            let mut next_rdt = rdt;
+            assert!(next_rdt.add_minute());
+            let (buffer, minute_length) = encode_time(&next_rdt).unwrap();
+            let report = decode_time(&mut rdt, &buffer, minute_length, true);
+            assert_eq!(report.day, FieldStatus::Ok);
+            assert_eq!(report.dut1, Some(0));
+            assert!(!rdt.get_jump_minute() && !rdt.get_jump_day() && !rdt.get_jump_year());
+        }
+        assert_eq!(rdt.get_year(), Some(0));
+        assert_eq!(rdt.get_month(), Some(1));
+        assert_eq!(rdt.get_day(), Some(1));
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn test_encode_time_dst_announced() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..=30 {
+            decode_time(
+                &mut rdt,
+                &frame(24, 70, 23, minute, (true, false), false),
+                60,
+                true,
+            );
+        }
+        assert_eq!(rdt.get_dst(), Some(DST_ANNOUNCED));
+        let (buffer, _) = encode_time(&rdt).unwrap();
+        assert_eq!((buffer[57], buffer[58]), (Some(true), Some(false)));
+        assert_eq!(get_bcd_value_u16(&buffer, &[(8, 5), (3, 1)]), Some(30));
+    }
+    #[test]
+    fn test_encode_time_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..=58 {
+            decode_time(
+                &mut rdt,
+                &frame(16, 366, 23, minute, (false, false), true),
+                60,
+                true,
+            );
+        }
+        let mut next_rdt = rdt;
+        assert!(next_rdt.add_minute());
+        let (buffer, minute_length) = encode_time(&next_rdt).unwrap();
+        assert_eq!(minute_length, 61);
+        assert_eq!(buffer[56], Some(true));
+        decode_time(&mut rdt, &buffer, minute_length, true);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED));
+    }
+    #[test]
+    fn test_encode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..=58 {
+            let mut buffer = frame(16, 366, 23, minute, (false, false), true);
+            // DUT1 = +0.7 s
+            buffer[36] = Some(true);
+            buffer[37] = Some(false);
+            buffer[38] = Some(true);
+            set_bcd_value(&mut buffer, 43, 40, 7);
+            decode_time(&mut rdt, &buffer, 60, true);
+        }
+        // The sign is not transmitted, a positive DUT1 does not imply it:
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED));
+        let mut sender = rdt_at(0, (16, 12, 31), (23, 59), false);
+        sender.set_signed_leap_second(Some(true), Some(true), 60);
+        assert!(sender.add_minute());
+        let (buffer, minute_length) = encode_time(&sender).unwrap();
+        assert_eq!(minute_length, 59);
+        let report = decode_time(&mut rdt, &buffer, minute_length, true);
+        assert_eq!(report.dut1, Some(5));
+        assert_eq!(report.leap_second, FieldStatus::Ok);
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(0)), None);
+    }
+}
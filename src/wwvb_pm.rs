@@ -0,0 +1,496 @@
+//! Decoder for the phase-modulated (BPSK) time code of WWVB (Fort Collins, Colorado, USA, 60 kHz).
+//!
+//! Since 2012, WWVB transmits one bit per second by reversing the phase of the carrier,
+//! independently of the amplitude-modulated time code. The bit buffer holds the phase
+//! bits, indexed by second:
+//!
+//! * 0 through 12: the sync word
+//! * 13 through 17: the Hamming parity bits of the time, MSB first
+//! * 18: bit 25 of the time, 19: bit 0 of the time
+//! * 20 through 28, 30 through 38, 40 through 45: bits 24 through 1 of the time
+//! * 29, 39, 57 through 59: reserved, always 0
+//! * 46 through 50: leap second warning, leap second sign, the two DST bits, and even parity
+//! * 51 through 56: the DST schedule
+//!
+//! The time is transmitted as the number of minutes since 2000-01-01 00:00 UTC, protected
+//! by a (31,26) Hamming code which corrects a single wrong or missing bit. Like the
+//! amplitude-modulated time code, it describes the minute it is transmitted in.
+//!
+//! The extended-mode frames, which replace the minute frames at a few fixed minutes in
+//! each half hour, are not decoded.
+
+use crate::radio_datetime_helpers::{civil_from_days, days_from_civil, get_weekday};
+use crate::utc::get_minute_length;
+use crate::wwvb::{get_dst, get_dst_bits};
+use crate::{
+    FieldStatus, RadioDateTimeUtils, BIT_BUFFER_SIZE, DST_SUMMER, LEAP_ANNOUNCED, LEAP_NEGATIVE,
+    LEAP_PROCESSED,
+};
+
+/// The sync word at the start of each minute frame.
+pub const SYNC_WORD: [bool; 13] = [
+    false, false, false, true, true, true, false, true, true, false, true, false, false,
+];
+
+/// Positions of the time bits, indexed by bit number.
+const TIME_BITS: [usize; 26] = [
+    19, 45, 44, 43, 42, 41, 40, 38, 37, 36, 35, 34, 33, 32, 31, 30, 28, 27, 26, 25, 24, 23, 22, 21,
+    20, 18,
+];
+
+/// Positions of the Hamming parity bits of the time, indexed by bit number.
+const PARITY_BITS: [usize; 5] = [17, 16, 15, 14, 13];
+
+/// Positions of the reserved bits, which are always 0.
+const RESERVED: [usize; 5] = [29, 39, 57, 58, 59];
+
+/// Number of minutes in the 21st century.
+const MINUTES_PER_CENTURY: u32 = 36_525 * 1440;
+
+/// Numeric value of Sunday, WWVB does not transmit the day of the week.
+const SUNDAY: u8 = 0;
+
+/// Per-field outcome of decoding one phase-modulated WWVB minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeReport {
+    pub minute: FieldStatus,
+    pub hour: FieldStatus,
+    pub day: FieldStatus,
+    pub weekday: FieldStatus,
+    pub month: FieldStatus,
+    pub year: FieldStatus,
+    pub dst: FieldStatus,
+    pub leap_second: FieldStatus,
+    /// position of the time bit corrected by the Hamming code, if any
+    pub corrected: Option<usize>,
+    /// the DST schedule, 0 through 63, None if absent
+    pub dst_schedule: Option<u8>,
+}
+
+/// Returns the Hamming code position (1 through 31) of the given time bit, the positions
+/// which are a power of two belong to the parity bits.
+///
+/// # Arguments
+/// * `bit` - the number of the time bit, 0 through 25
+fn get_code_position(bit: usize) -> u8 {
+    (3..=31u8)
+        .filter(|p| !p.is_power_of_two())
+        .nth(bit)
+        .unwrap()
+}
+
+/// Returns the Hamming parity bits for the given time value.
+///
+/// # Arguments
+/// * `time` - the number of minutes since 2000-01-01 00:00 UTC, 26 bits
+pub fn get_hamming_parity(time: u32) -> u8 {
+    (0..TIME_BITS.len())
+        .filter(|bit| (time >> bit) & 1 == 1)
+        .fold(0, |parity, bit| parity ^ get_code_position(bit))
+}
+
+/// Decode the time from the given bit buffer, correcting a single wrong or missing bit
+/// in place.
+///
+/// Returns the number of minutes since 2000-01-01 00:00 UTC and the position of the
+/// corrected bit, if any, or None if more than one bit is missing.
+///
+/// # Arguments
+/// * `bit_buffer` - the bits of the minute, indexed by second
+fn get_minute_of_century(
+    bit_buffer: &mut [Option<bool>; BIT_BUFFER_SIZE],
+) -> Option<(u32, Option<usize>)> {
+    let missing = TIME_BITS
+        .iter()
+        .chain(PARITY_BITS.iter())
+        .filter(|idx| bit_buffer[**idx].is_none())
+        .count();
+    if missing > 1 {
+        return None;
+    }
+    let value = |positions: &[usize]| {
+        positions
+            .iter()
+            .enumerate()
+            .filter(|(_, idx)| bit_buffer[**idx] == Some(true))
+            .fold(0u32, |acc, (bit, _)| acc | 1 << bit)
+    };
+    let mut time = value(&TIME_BITS);
+    let parity = value(&PARITY_BITS) as u8;
+    let syndrome = parity ^ get_hamming_parity(time);
+    let corrected = if syndrome == 0 {
+        None
+    } else if syndrome.is_power_of_two() {
+        Some(PARITY_BITS[syndrome.trailing_zeros() as usize])
+    } else {
+        let bit = (0..TIME_BITS.len()).find(|bit| get_code_position(*bit) == syndrome)?;
+        time ^= 1 << bit;
+        Some(TIME_BITS[bit])
+    };
+    if let Some(idx) = corrected {
+        bit_buffer[idx] = bit_buffer[idx].map_or(Some(true), |b| Some(!b));
+    }
+    // The bit was missing but correct when taken as 0:
+    for idx in TIME_BITS.iter().chain(PARITY_BITS.iter()) {
+        bit_buffer[*idx].get_or_insert(false);
+    }
+    Some((time, corrected))
+}
+
+/// Returns the value of the given bits, MSB first, or None if any of them is missing.
+///
+/// # Arguments
+/// * `bits` - the bits, at most 8
+fn get_binary_value(bits: &[Option<bool>]) -> Option<u8> {
+    bits.iter()
+        .try_fold(0, |acc, b| Some(acc << 1 | (*b)? as u8))
+}
+
+/// Returns the leap second and DST bits 46 through 49 as a bitmask, with the leap second
+/// warning as bit 3 and the DST bits as bits 1 and 0, or None if any of them is missing
+/// or if the parity in bit 50 is wrong.
+///
+/// # Arguments
+/// * `bit_buffer` - the bits of the minute, indexed by second
+fn get_dst_ls(bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE]) -> Option<u8> {
+    let value = get_binary_value(&bit_buffer[46..=49])?;
+    (value.count_ones() % 2 == bit_buffer[50]? as u32).then_some(value)
+}
+
+/// Decode the bit buffer of one phase-modulated WWVB minute into the given date/time
+/// instance.
+///
+/// Like with the amplitude-modulated time code, the date/time is set to the minute
+/// starting at the end of the bit buffer, the day of the week is calculated from the
+/// date with Sunday being 0, and DST changes are applied at 00:00 UTC. A single wrong
+/// or missing bit of the time is corrected.
+///
+/// The date/time is first advanced by one minute using `add_minute_utc()`, so the decoded
+/// values can be checked for jumps against the expected ones.
+///
+/// # Arguments
+/// * `rdt` - the date/time to update, should be created using `RadioDateTimeUtils::new(0)`
+/// * `bit_buffer` - the phase bits of the minute, indexed by second
+/// * `minute_length` - the length of the decoded minute in seconds, 59 through 61
+/// * `check_jump` - check if the values have jumped unexpectedly compared to `add_minute()`.
+pub fn decode_time(
+    rdt: &mut RadioDateTimeUtils,
+    bit_buffer: &[Option<bool>; BIT_BUFFER_SIZE],
+    minute_length: u8,
+    check_jump: bool,
+) -> DecodeReport {
+    // WWVB transmits UTC, so the local hour change of add_minute() does not apply:
+    let added_minute = rdt.add_minute_utc();
+    let check_jump = check_jump && added_minute;
+    let frame_ok = (59..=61).contains(&minute_length)
+        && bit_buffer[..SYNC_WORD.len()]
+            .iter()
+            .zip(SYNC_WORD)
+            .all(|(b, s)| *b == Some(s))
+        && RESERVED.iter().all(|idx| bit_buffer[*idx] != Some(true));
+
+    let mut bits = *bit_buffer;
+    let decoded = get_minute_of_century(&mut bits)
+        .filter(|(time, _)| frame_ok && *time < MINUTES_PER_CENTURY);
+    let bit_buffer = &bits;
+    // The time describes the minute it is transmitted in, so add one minute:
+    let time = decoded.map(|(time, _)| time + 1);
+    let days = time.map(|t| days_from_civil(2000, 1, 1) + (t / 1440) as i32);
+    let date = days.map(civil_from_days);
+    let year = date.map(|d| (d.0 % 100) as u8);
+    let month = date.map(|d| d.1);
+    let day = date.map(|d| d.2);
+    let hour = time.map(|t| (t % 1440 / 60) as u8);
+    let minute = time.map(|t| (t % 60) as u8);
+    let weekday = date.and_then(|(y, m, d)| get_weekday((y % 100) as u8, m, d, SUNDAY));
+
+    rdt.set_minute(minute, true, check_jump);
+    rdt.set_hour(hour, true, check_jump);
+    rdt.set_year(year, true, check_jump);
+    rdt.set_month(month, true, check_jump);
+    rdt.set_weekday(weekday, true, check_jump);
+    // day must be set *after* year, month, and weekday
+    rdt.set_day(day, true, check_jump);
+
+    let dst_ls = get_dst_ls(bit_buffer).filter(|_| frame_ok);
+    let (dst, dst_announce) = get_dst(
+        dst_ls.map(|v| v & 2 != 0),
+        dst_ls.map(|v| v & 1 != 0),
+        hour,
+        minute,
+    );
+    rdt.set_dst(dst, dst_announce, check_jump);
+    // The leap second warning stays set for the whole month, only count it during the
+    // hour before the change:
+    let last_day = days.map(|d| civil_from_days(d + 1).2 == 1);
+    let leap_announce = dst_ls.map(|v| v & 8 != 0 && hour == Some(23) && last_day == Some(true));
+    rdt.set_signed_leap_second(leap_announce, dst_ls.map(|v| v & 4 != 0), minute_length);
+    rdt.bump_minutes_running();
+
+    let time_status = |value: Option<u8>, stored: Option<u8>| {
+        FieldStatus::new(&bit_buffer[13..=45], Some(true), value, stored)
+    };
+    DecodeReport {
+        minute: time_status(minute, rdt.get_minute()),
+        hour: time_status(hour, rdt.get_hour()),
+        day: time_status(day, rdt.get_day()),
+        weekday: time_status(weekday, rdt.get_weekday()),
+        month: time_status(month, rdt.get_month()),
+        year: time_status(year, rdt.get_year()),
+        dst: FieldStatus::new(
+            &bit_buffer[46..=50],
+            Some(dst_ls.is_some()),
+            dst.map(|d| d as u8),
+            rdt.get_dst().map(|d| (d & DST_SUMMER != 0) as u8),
+        ),
+        leap_second: FieldStatus::new(
+            &bit_buffer[46..=50],
+            Some(dst_ls.is_some()),
+            Some(minute_length).filter(|m| (59..=61).contains(m)),
+            Some(minute_length),
+        ),
+        corrected: decoded.and_then(|(_, corrected)| corrected),
+        dst_schedule: get_binary_value(&bit_buffer[51..=56]),
+    }
+}
+
+/// Encode the given date/time into the phase bits of one WWVB minute, the inverse of
+/// `decode_time()`.
+///
+/// The transmitted minute is the one before `rdt`. An announced DST change is encoded
+/// as taking effect at the end of the transmitted day. The DST schedule is encoded as 0
+/// and the bits after the end of the minute are None.
+///
+/// Returns the bit buffer and the length of the minute in seconds, or None if the
+/// date/time is not valid or is 2000-01-01 00:00.
+///
+/// # Arguments
+/// * `rdt` - the date/time to encode, which is the minute starting at the end of the buffer
+pub fn encode_time(rdt: &RadioDateTimeUtils) -> Option<([Option<bool>; BIT_BUFFER_SIZE], u8)> {
+    if !rdt.is_valid() {
+        return None;
+    }
+    let minute = rdt.get_minute().unwrap();
+    let hour = rdt.get_hour().unwrap();
+    // Years are limited to 2 digits, assume the 21st century:
+    let days = days_from_civil(
+        2000 + rdt.get_year().unwrap() as u16,
+        rdt.get_month().unwrap(),
+        rdt.get_day().unwrap(),
+    ) - days_from_civil(2000, 1, 1);
+    let time = (days as u32 * 1440 + hour as u32 * 60 + minute as u32).checked_sub(1)?;
+    let dst = rdt.get_dst().unwrap();
+    let dst_bits = get_dst_bits(dst, hour, minute);
+    let leap_second = rdt.get_leap_second().unwrap_or(0);
+    let leap_warning =
+        leap_second & LEAP_ANNOUNCED != 0 || minute == 0 && leap_second & LEAP_PROCESSED != 0;
+    let minute_length = get_minute_length(leap_second, minute);
+
+    let mut bit_buffer = [Some(false); BIT_BUFFER_SIZE];
+    for bit in &mut bit_buffer[60..] {
+        *bit = None;
+    }
+    for (idx, sync) in SYNC_WORD.iter().enumerate() {
+        bit_buffer[idx] = Some(*sync);
+    }
+    for (bit, idx) in TIME_BITS.iter().enumerate() {
+        bit_buffer[*idx] = Some((time >> bit) & 1 == 1);
+    }
+    let parity = get_hamming_parity(time);
+    for (bit, idx) in PARITY_BITS.iter().enumerate() {
+        bit_buffer[*idx] = Some((parity >> bit) & 1 == 1);
+    }
+    bit_buffer[46] = Some(leap_warning);
+    bit_buffer[47] = Some(leap_second & LEAP_NEGATIVE != 0);
+    bit_buffer[48] = Some(dst_bits.0);
+    bit_buffer[49] = Some(dst_bits.1);
+    bit_buffer[50] = Some(
+        bit_buffer[46..=49]
+            .iter()
+            .filter(|b| **b == Some(true))
+            .count()
+            % 2
+            == 1,
+    );
+    Some((bit_buffer, minute_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radio_datetime_helpers::set_parity;
+    use crate::tests::rdt_at;
+    use crate::DST_PROCESSED;
+
+    /// Build the minute transmitted at the given date/time, with the DST bits 48/49 and
+    /// the leap second warning and sign.
+    fn frame(
+        (year, month, day, hour, minute): (u8, u8, u8, u8, u8),
+        dst: (bool, bool),
+        lsw: bool,
+        negative: bool,
+    ) -> [Option<bool>; BIT_BUFFER_SIZE] {
+        let mut rdt = rdt_at(0, (year, month, day), (hour, minute), false);
+        assert!(rdt.add_minute());
+        let (mut buffer, _) = encode_time(&rdt).unwrap();
+        buffer[46] = Some(lsw);
+        buffer[47] = Some(negative);
+        buffer[48] = Some(dst.0);
+        buffer[49] = Some(dst.1);
+        set_parity(&mut buffer, 46, 49, 50, false);
+        buffer
+    }
+
+    #[test]
+    fn test_hamming_parity() {
+        assert_eq!(get_hamming_parity(0), 0);
+        assert_eq!(get_hamming_parity(1), 3);
+        assert_eq!(get_hamming_parity(0b11), 3 ^ 5);
+        assert_eq!(get_hamming_parity(1 << 25), 31);
+    }
+    #[test]
+    fn test_decode_time_regular() {
+        // Thursday 2024-02-29 12:34 UTC, so 12:35 at the end of the minute
+        let (bits, minute_length) = encode_time(&rdt_at(0, (24, 2, 29), (12, 35), false)).unwrap();
+        // 2024-02-29 is day 8825 since 2000-01-01:
+        let time = 8825 * 1440 + 12 * 60 + 34;
+        let (decoded, corrected) = get_minute_of_century(&mut bits.clone()).unwrap();
+        assert_eq!(decoded, time);
+        assert_eq!(corrected, None);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bits, minute_length, false);
+        assert_eq!(rdt.get_year(), Some(24));
+        assert_eq!(rdt.get_month(), Some(2));
+        assert_eq!(rdt.get_day(), Some(29));
+        assert_eq!(rdt.get_weekday(), Some(4));
+        assert_eq!(rdt.get_hour(), Some(12));
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert_eq!(rdt.get_dst(), Some(0));
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(report.dst, FieldStatus::Ok);
+        assert_eq!(report.corrected, None);
+        assert_eq!(report.dst_schedule, Some(0));
+    }
+    #[test]
+    fn test_decode_time_new_year() {
+        let (bits, minute_length) = encode_time(&rdt_at(0, (25, 1, 1), (0, 0), false)).unwrap();
+        let mut rdt = RadioDateTimeUtils::new(0);
+        decode_time(&mut rdt, &bits, minute_length, false);
+        assert_eq!(rdt.get_year(), Some(25));
+        assert_eq!(rdt.get_month(), Some(1));
+        assert_eq!(rdt.get_day(), Some(1));
+        assert_eq!(rdt.get_weekday(), Some(3));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_minute(), Some(0));
+    }
+    #[test]
+    fn continue_decode_time_corrected() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let (bits, minute_length) = encode_time(&rdt_at(0, (24, 6, 16), (12, 34), false)).unwrap();
+        decode_time(&mut rdt, &bits, minute_length, false);
+        // each single bit error is corrected:
+        for idx in TIME_BITS.iter().chain(PARITY_BITS.iter()) {
+            let mut bad = rdt;
+            let (mut bits, _) = encode_time(&rdt_at(0, (24, 6, 16), (12, 35), false)).unwrap();
+            bits[*idx] = bits[*idx].map(|b| !b);
+            let report = decode_time(&mut bad, &bits, minute_length, true);
+            assert_eq!(report.corrected, Some(*idx));
+            assert_eq!(report.minute, FieldStatus::Ok);
+            assert_eq!(bad.get_minute(), Some(35));
+            assert!(!bad.get_jump_minute());
+        }
+        // a missing bit as well:
+        let (mut bits, _) = encode_time(&rdt_at(0, (24, 6, 16), (12, 35), false)).unwrap();
+        bits[20] = None;
+        let report = decode_time(&mut rdt, &bits, minute_length, true);
+        assert_eq!(report.minute, FieldStatus::Ok);
+        assert_eq!(rdt.get_minute(), Some(35));
+        assert!(!rdt.get_jump_minute());
+    }
+    #[test]
+    fn test_decode_time_too_many_missing() {
+        let (mut bits, minute_length) =
+            encode_time(&rdt_at(0, (24, 6, 16), (12, 34), false)).unwrap();
+        bits[20] = None;
+        bits[30] = None;
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bits, minute_length, false);
+        assert_eq!(rdt.get_minute(), None);
+        assert_eq!(report.minute, FieldStatus::Missing);
+        assert_eq!(report.corrected, None);
+    }
+    #[test]
+    fn test_decode_time_bad_sync() {
+        let (mut bits, minute_length) =
+            encode_time(&rdt_at(0, (24, 6, 16), (12, 34), false)).unwrap();
+        bits[3] = Some(false);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bits, minute_length, false);
+        assert_eq!(rdt.get_minute(), None);
+        assert_eq!(rdt.get_dst(), None);
+        assert_eq!(report.minute, FieldStatus::Invalid);
+    }
+    #[test]
+    fn test_decode_time_bad_dst_parity() {
+        let (mut bits, minute_length) =
+            encode_time(&rdt_at(0, (24, 6, 16), (12, 34), false)).unwrap();
+        bits[48] = Some(true);
+        let mut rdt = RadioDateTimeUtils::new(0);
+        let report = decode_time(&mut rdt, &bits, minute_length, false);
+        assert_eq!(rdt.get_minute(), Some(34));
+        assert_eq!(rdt.get_dst(), None);
+        assert_eq!(report.dst, FieldStatus::BadParity);
+    }
+    #[test]
+    fn continue_decode_time_dst_begins() {
+        // DST starts at 00:00 UTC on 2024-03-10
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..60 {
+            let bits = frame((24, 3, 9, 23, minute), (true, false), false, false);
+            decode_time(&mut rdt, &bits, 60, true);
+        }
+        assert_eq!(rdt.get_day(), Some(10));
+        assert_eq!(rdt.get_hour(), Some(0));
+        assert_eq!(rdt.get_minute(), Some(0));
+        assert!(!rdt.get_jump_minute() && !rdt.get_jump_hour() && !rdt.get_jump_day());
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER | DST_PROCESSED));
+        let bits = frame((24, 3, 10, 0, 0), (true, true), false, false);
+        decode_time(&mut rdt, &bits, 60, true);
+        assert_eq!(rdt.get_minute(), Some(1));
+        assert_eq!(rdt.get_dst(), Some(DST_SUMMER));
+    }
+    #[test]
+    fn continue_decode_time_negative_leap_second() {
+        let mut rdt = RadioDateTimeUtils::new(0);
+        for minute in 0..59 {
+            let bits = frame((24, 6, 30, 23, minute), (false, false), true, true);
+            decode_time(&mut rdt, &bits, 60, true);
+        }
+        assert_eq!(rdt.get_minute(), Some(59));
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_ANNOUNCED | LEAP_NEGATIVE));
+        let bits = frame((24, 6, 30, 23, 59), (false, false), true, true);
+        decode_time(&mut rdt, &bits, 59, true);
+        assert_eq!(rdt.get_minute(), Some(0));
+        assert_eq!(rdt.get_leap_second(), Some(LEAP_PROCESSED | LEAP_NEGATIVE));
+    }
+    #[test]
+    fn test_encode_time_round_trip() {
+        let mut expected = rdt_at(0, (24, 6, 30), (23, 59), false);
+        expected.set_signed_leap_second(Some(true), Some(true), 60);
+        let (bits, minute_length) = encode_time(&expected).unwrap();
+        assert_eq!(minute_length, 60);
+        assert_eq!(bits, frame((24, 6, 30, 23, 58), (false, false), true, true));
+        // DST begins at the end of the transmitted day:
+        expected.set_dst(Some(true), Some(true), false);
+        let (bits, _) = encode_time(&expected).unwrap();
+        assert_eq!(
+            bits[46..=50],
+            [Some(true), Some(true), Some(true), Some(false), Some(true)]
+        );
+    }
+    #[test]
+    fn bad_encode_time() {
+        assert_eq!(encode_time(&RadioDateTimeUtils::new(0)), None);
+        assert_eq!(encode_time(&rdt_at(0, (0, 1, 1), (0, 0), false)), None);
+    }
+}